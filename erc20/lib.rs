@@ -5,7 +5,10 @@ use ink_lang as ink;
 #[ink::contract]
 mod erc20 {
 
-    use ink_storage::{ traits::SpreadAllocate, Mapping };
+    use ink_storage::{
+        traits::{ SpreadAllocate, PackedLayout, SpreadLayout },
+        Mapping
+    };
 
     #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
     #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
@@ -13,10 +16,124 @@ mod erc20 {
         InsufficientBalance,
         AttemptingSelfTransfer,
         InsufficientAllowance,
-        AttemptingSelfAllowance
+        AttemptingSelfAllowance,
+
+        Overflow,
+        StreamNotFound,
+        AttemptingSelfStream,
+        InvalidStreamDeposit,
+        InvalidStreamTime,
+        NotStreamParty,
+        InsufficientStreamBalance,
+
+        NotSigner,
+        ProposalNotFound,
+        ProposalExpired,
+        ProposalAlreadyExecuted,
+        AlreadyConfirmed,
+        NotConfirmed,
+        ThresholdNotReached,
+        InvalidSigners,
+        InvalidThreshold,
+
+        RebasingDisabled,
+        NotRebaser,
+        InvalidSupply,
+        RebaseTooImprecise,
+        UnsupportedWhenRebasing,
+
+        RateLimited,
+        InvalidOutflowLimit,
+
+        NotHoldOperator,
+        HoldAlreadyExists,
+        HoldNotFound,
+        HoldExpired,
+        InvalidHoldExpiry
     }
 
     pub type Result<T> = core::result::Result<T, Error>;
+    pub type StreamId = u64;
+    pub type StreamIdList = ink_prelude::vec::Vec<StreamId>;
+    pub type ProposalId = u64;
+    pub type AccountIdList = ink_prelude::vec::Vec<AccountId>;
+    pub type HoldId = Hash;
+
+    // proposals not executed within a week can no longer be confirmed or executed.
+    const PROPOSAL_LIFETIME: Timestamp = 7 * 24 * 60 * 60 * 1000;
+    // rebasing balances start with this many shares per unit,
+    // so supply can grow up to this factor before shares lose precision.
+    const INITIAL_SHARES_PER_UNIT: Balance = 1_000_000_000;
+    // a rebase may miss the requested supply by at most 1 / REBASE_TOLERANCE of it.
+    const REBASE_TOLERANCE: Balance = 1_000;
+
+    #[derive(
+        Debug, PartialEq, Eq, Clone, Copy,
+        scale::Encode, scale::Decode, SpreadLayout, PackedLayout
+    )]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct Stream {
+        sender: AccountId,
+        recipient: AccountId,
+        deposit: Balance,
+        withdrawn: Balance,
+        start: Timestamp,
+        stop: Timestamp
+    }
+
+    #[derive(
+        Debug, PartialEq, Eq, Clone, Copy,
+        scale::Encode, scale::Decode, SpreadLayout, PackedLayout
+    )]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct Hold {
+        owner: AccountId,
+        operator: AccountId,
+        amount: Balance,
+        expires_at: Timestamp
+    }
+
+    /// At most `amount` may leave an account per `window` milliseconds.
+    #[derive(
+        Debug, PartialEq, Eq, Clone, Copy,
+        scale::Encode, scale::Decode, SpreadLayout, PackedLayout
+    )]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct OutflowLimit {
+        pub amount: Balance,
+        pub window: Timestamp
+    }
+
+    #[derive(
+        Debug, PartialEq, Eq, Clone,
+        scale::Encode, scale::Decode, SpreadLayout, PackedLayout
+    )]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum AdminAction {
+        Mint { to: AccountId, value: Balance },
+        Burn { from: AccountId, value: Balance },
+        ChangeSigners(AccountIdList),
+        ChangeThreshold(u32),
+        ChangeRebaser(AccountId),
+        SetDefaultOutflowLimit(Option<OutflowLimit>),
+        SetOutflowLimit { account: AccountId, limit: Option<OutflowLimit> }
+    }
+
+    #[derive(
+        Debug, PartialEq, Eq, Clone,
+        scale::Encode, scale::Decode, SpreadLayout, PackedLayout
+    )]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct Proposal {
+        action: AdminAction,
+        proposer: AccountId,
+        confirmations: AccountIdList,
+        expires_at: Timestamp,
+        executed: bool
+    }
 
     #[ink(event)]
     pub struct Transfer {
@@ -36,6 +153,126 @@ mod erc20 {
         value: Balance
     }
 
+    #[ink(event)]
+    pub struct CreateStream {
+        #[ink(topic)]
+        stream_id: StreamId,
+        #[ink(topic)]
+        sender: AccountId,
+        #[ink(topic)]
+        recipient: AccountId,
+        deposit: Balance,
+        start: Timestamp,
+        stop: Timestamp
+    }
+
+    #[ink(event)]
+    pub struct WithdrawFromStream {
+        #[ink(topic)]
+        stream_id: StreamId,
+        #[ink(topic)]
+        recipient: AccountId,
+        value: Balance
+    }
+
+    #[ink(event)]
+    pub struct CancelStream {
+        #[ink(topic)]
+        stream_id: StreamId,
+        #[ink(topic)]
+        sender: AccountId,
+        #[ink(topic)]
+        recipient: AccountId,
+        sender_balance: Balance,
+        recipient_balance: Balance
+    }
+
+    #[ink(event)]
+    pub struct Proposed {
+        #[ink(topic)]
+        proposal_id: ProposalId,
+        #[ink(topic)]
+        proposer: AccountId,
+        action: AdminAction,
+        expires_at: Timestamp
+    }
+
+    #[ink(event)]
+    pub struct Confirmed {
+        #[ink(topic)]
+        proposal_id: ProposalId,
+        #[ink(topic)]
+        signer: AccountId
+    }
+
+    #[ink(event)]
+    pub struct ConfirmationRevoked {
+        #[ink(topic)]
+        proposal_id: ProposalId,
+        #[ink(topic)]
+        signer: AccountId
+    }
+
+    #[ink(event)]
+    pub struct Executed {
+        #[ink(topic)]
+        proposal_id: ProposalId,
+        #[ink(topic)]
+        executor: AccountId
+    }
+
+    #[ink(event)]
+    pub struct Rebase {
+        #[ink(topic)]
+        rebaser: AccountId,
+        total_supply: Balance,
+        shares_per_unit: Balance
+    }
+
+    #[ink(event)]
+    pub struct HoldOperatorAuthorization {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        operator: AccountId,
+        authorized: bool
+    }
+
+    #[ink(event)]
+    pub struct HoldCreated {
+        #[ink(topic)]
+        hold_id: HoldId,
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        operator: AccountId,
+        amount: Balance,
+        expires_at: Timestamp
+    }
+
+    #[ink(event)]
+    pub struct HoldReleased {
+        #[ink(topic)]
+        hold_id: HoldId,
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        operator: AccountId,
+        amount: Balance
+    }
+
+    #[ink(event)]
+    pub struct HoldExecuted {
+        #[ink(topic)]
+        hold_id: HoldId,
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+        operator: AccountId,
+        amount: Balance
+    }
+
     /// Defines the storage of your contract.
     /// Add new fields to the below struct in order
     /// to add new static storage fields to your contract.
@@ -43,8 +280,39 @@ mod erc20 {
     #[derive(SpreadAllocate)]
     pub struct Erc20 {
         total_supply: Balance,
+        // balances are held in shares, see `shares_per_unit`.
         balances: Mapping<AccountId, Balance>,
-        allowance: Mapping<(/*owner*/AccountId, /*spender*/AccountId), Balance>
+        allowance: Mapping<(/*owner*/AccountId, /*spender*/AccountId), Balance>,
+
+        // deposits are escrowed on the contract's own account.
+        next_stream_id: StreamId,
+        streams: Mapping<StreamId, Stream>,
+        sender_streams: Mapping<AccountId, StreamIdList>,
+        recipient_streams: Mapping<AccountId, StreamIdList>,
+
+        // privileged operations need `threshold` of `signers` to confirm.
+        signers: AccountIdList,
+        threshold: u32,
+        next_proposal_id: ProposalId,
+        proposals: Mapping<ProposalId, Proposal>,
+
+        // external units = shares / shares_per_unit.
+        // without rebaser this stays 1 and shares are units.
+        rebaser: Option<AccountId>,
+        total_shares: Balance,
+        shares_per_unit: Balance,
+
+        // per-account limit overrides the default one.
+        default_outflow_limit: Option<OutflowLimit>,
+        outflow_limits: Mapping<AccountId, OutflowLimit>,
+        // (outflow, last update), outflow drains linearly over the window.
+        outflows: Mapping<AccountId, (Balance, Timestamp)>,
+
+        // held funds stay in the owner's balance but cannot be spent.
+        hold_operators: Mapping<(/*owner*/AccountId, /*operator*/AccountId), ()>,
+        // hold ids are chosen by the operator, so they are only unique per operator.
+        holds: Mapping<(/*operator*/AccountId, HoldId), Hold>,
+        held_balances: Mapping<AccountId, Balance>
     }
 
     // constructors
@@ -52,14 +320,29 @@ mod erc20 {
         #[ink(constructor)]
         pub fn new(init_supply: Balance) -> Self {
             ink_lang::utils::initialize_contract(|cnt: &mut Self| {
-                cnt.new_init_impl(init_supply)
+                cnt.new_init_impl(init_supply, None)
+            })
+        }
+
+        /// Creates the token in rebasing mode, `rebaser` can adjust supply later.
+        #[ink(constructor)]
+        pub fn new_rebasing(init_supply: Balance, rebaser: AccountId) -> Self {
+            ink_lang::utils::initialize_contract(|cnt: &mut Self| {
+                cnt.new_init_impl(init_supply, Some(rebaser))
             })
         }
         
-        fn new_init_impl(&mut self, init_supply: Balance) {
+        fn new_init_impl(&mut self, init_supply: Balance, rebaser: Option<AccountId>) {
             let caller = Self::env().caller();
-            self.balances.insert(&caller, &init_supply);
+            self.shares_per_unit = if rebaser.is_some() { INITIAL_SHARES_PER_UNIT } else { 1 };
+            self.rebaser = rebaser;
+            self.total_shares = init_supply
+                .checked_mul(self.shares_per_unit)
+                .expect("initial supply is too large for rebasing");
+            self.balances.insert(&caller, &self.total_shares);
             self.total_supply = init_supply;
+            self.signers = ink_prelude::vec![caller];
+            self.threshold = 1;
             self.env().emit_event(Transfer {
                 from: None,
                 to: Some(caller),
@@ -71,7 +354,7 @@ mod erc20 {
         #[ink(constructor)]
         pub fn default() -> Self {
             ink_lang::utils::initialize_contract(|cnt: &mut Self| {
-                cnt.new_init_impl(u128::MAX)
+                cnt.new_init_impl(u128::MAX, None)
             })
         }
 
@@ -83,9 +366,24 @@ mod erc20 {
 
         #[inline]
         fn balance_of_impl(&self, owner: &AccountId) -> Balance {
+            self.shares_of_impl(owner) / self.shares_per_unit
+        }
+
+        #[ink(message)]
+        pub fn shares_of(&self, owner: AccountId) -> Balance {
+            self.shares_of_impl(&owner)
+        }
+
+        #[inline]
+        fn shares_of_impl(&self, owner: &AccountId) -> Balance {
             self.balances.get(owner).unwrap_or_default()
         }
 
+        #[inline]
+        fn to_shares_impl(&self, value: Balance) -> Result<Balance> {
+            value.checked_mul(self.shares_per_unit).ok_or(Error::Overflow)
+        }
+
         /// Simply returns the current value
         #[ink(message)]
         pub fn total_supply(&self) -> Balance {
@@ -121,25 +419,45 @@ mod erc20 {
             }
 
             self.transfer_from_to(&from, &to, value)?;
-            self.allowance.insert((&from, &to), &(allowance - value));
+            // allowance is kept in external units
+            self.allowance.insert((&from, &caller), &(allowance - value));
             Ok(())
         }
 
+        #[inline]
         fn transfer_from_to(
             &mut self,
             from: &AccountId,
             to: &AccountId,
             value: Balance
         ) -> Result<()> {
-            let src_balance = self.balance_of_impl(from);
+            self.transfer_from_to_impl(from, to, value, 0)
+        }
+
+        // `released` is the part of from's held balance this transfer may spend.
+        fn transfer_from_to_impl(
+            &mut self,
+            from: &AccountId,
+            to: &AccountId,
+            value: Balance,
+            released: Balance
+        ) -> Result<()> {
+            let shares = self.to_shares_impl(value)?;
+            let src_shares = self.shares_of_impl(from);
             // ensure from has enough balance
-            if src_balance < value {
+            if src_shares < shares {
+                return Err(Error::InsufficientBalance)
+            }
+            // ensure held funds are not spent
+            let held = self.balance_on_hold_impl(from) - released;
+            if self.balance_of_impl(from).saturating_sub(held) < value {
                 return Err(Error::InsufficientBalance)
             }
+            self.record_outflow_impl(from, value)?;
 
-            self.balances.insert(from, &(src_balance - value));
-            let dst_balance = self.balance_of_impl(to);
-            self.balances.insert(to, &(dst_balance + value));
+            self.balances.insert(from, &(src_shares - shares));
+            let dst_shares = self.shares_of_impl(to);
+            self.balances.insert(to, &(dst_shares + shares));
             self.env().emit_event(Transfer{
                 from: Some(*from),
                 to: Some(*to),
@@ -177,6 +495,671 @@ mod erc20 {
         fn allowance_impl(&self, owner: &AccountId, spender: &AccountId) -> Balance {
             self.allowance.get((owner, spender)).unwrap_or_default()
         }
+
+        // streaming, deposits are fixed amounts so not in rebasing mode.
+        #[ink(message)]
+        pub fn create_stream(
+            &mut self,
+            recipient: AccountId,
+            deposit: Balance,
+            start: Timestamp,
+            stop: Timestamp
+        ) -> Result<StreamId> {
+            if self.rebaser.is_some() {
+                return Err(Error::UnsupportedWhenRebasing)
+            }
+            let sender = self.env().caller();
+            let contract = self.env().account_id();
+            // ensure sender is not recipient
+            if sender == recipient {
+                return Err(Error::AttemptingSelfStream)
+            }
+            if recipient == contract {
+                return Err(Error::AttemptingSelfTransfer)
+            }
+            if deposit == 0 {
+                return Err(Error::InvalidStreamDeposit)
+            }
+            // ensure stream does not start in the past
+            if start < self.env().block_timestamp() || stop <= start {
+                return Err(Error::InvalidStreamTime)
+            }
+
+            let stream_id = self.next_stream_id;
+            self.next_stream_id = stream_id.checked_add(1).ok_or(Error::Overflow)?;
+            self.transfer_from_to(&sender, &contract, deposit)?;
+
+            self.streams.insert(stream_id, &Stream {
+                sender,
+                recipient,
+                deposit,
+                withdrawn: 0,
+                start,
+                stop
+            });
+            Self::push_stream_id_impl(&mut self.sender_streams, &sender, stream_id);
+            Self::push_stream_id_impl(&mut self.recipient_streams, &recipient, stream_id);
+            self.env().emit_event(CreateStream {
+                stream_id,
+                sender,
+                recipient,
+                deposit,
+                start,
+                stop
+            });
+            Ok(stream_id)
+        }
+
+        #[ink(message)]
+        pub fn get_stream(&self, stream_id: StreamId) -> Option<Stream> {
+            self.streams.get(stream_id)
+        }
+
+        #[ink(message)]
+        pub fn streams_of_sender(&self, sender: AccountId) -> StreamIdList {
+            self.sender_streams.get(sender).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn streams_of_recipient(&self, recipient: AccountId) -> StreamIdList {
+            self.recipient_streams.get(recipient).unwrap_or_default()
+        }
+
+        /// Returns what `who` could take out of the stream right now.
+        /// Recipient gets streamed minus withdrawn, sender gets the unstreamed rest.
+        #[ink(message)]
+        pub fn balance_of_stream(&self, stream_id: StreamId, who: AccountId
+        ) -> Result<Balance> {
+            let stream = self.streams.get(stream_id).ok_or(Error::StreamNotFound)?;
+            let (sender_balance, recipient_balance) = self.stream_balances_impl(&stream);
+            if who == stream.recipient {
+                Ok(recipient_balance)
+            } else if who == stream.sender {
+                Ok(sender_balance)
+            } else {
+                Ok(0)
+            }
+        }
+
+        #[ink(message)]
+        pub fn withdraw_from_stream(&mut self, stream_id: StreamId, value: Balance
+        ) -> Result<()> {
+            let mut stream = self.streams.get(stream_id).ok_or(Error::StreamNotFound)?;
+            // only recipient can withdraw
+            if self.env().caller() != stream.recipient {
+                return Err(Error::NotStreamParty)
+            }
+            let (_, recipient_balance) = self.stream_balances_impl(&stream);
+            if recipient_balance < value {
+                return Err(Error::InsufficientStreamBalance)
+            }
+
+            let contract = self.env().account_id();
+            self.transfer_from_to(&contract, &stream.recipient, value)?;
+            stream.withdrawn += value;
+            if stream.withdrawn == stream.deposit {
+                self.remove_stream_impl(stream_id, &stream);
+            } else {
+                self.streams.insert(stream_id, &stream);
+            }
+            self.env().emit_event(WithdrawFromStream {
+                stream_id,
+                recipient: stream.recipient,
+                value
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn cancel_stream(&mut self, stream_id: StreamId) -> Result<()> {
+            let stream = self.streams.get(stream_id).ok_or(Error::StreamNotFound)?;
+            // either side can cancel
+            let caller = self.env().caller();
+            if caller != stream.sender && caller != stream.recipient {
+                return Err(Error::NotStreamParty)
+            }
+
+            let (sender_balance, recipient_balance) = self.stream_balances_impl(&stream);
+            let contract = self.env().account_id();
+            if recipient_balance > 0 {
+                self.transfer_from_to(&contract, &stream.recipient, recipient_balance)?;
+            }
+            if sender_balance > 0 {
+                self.transfer_from_to(&contract, &stream.sender, sender_balance)?;
+            }
+            self.remove_stream_impl(stream_id, &stream);
+            self.env().emit_event(CancelStream {
+                stream_id,
+                sender: stream.sender,
+                recipient: stream.recipient,
+                sender_balance,
+                recipient_balance
+            });
+            Ok(())
+        }
+
+        // multisig
+        #[ink(message)]
+        pub fn signers(&self) -> AccountIdList {
+            self.signers.clone()
+        }
+
+        #[ink(message)]
+        pub fn threshold(&self) -> u32 {
+            self.threshold
+        }
+
+        #[ink(message)]
+        pub fn get_proposal(&self, proposal_id: ProposalId) -> Option<Proposal> {
+            self.proposals.get(proposal_id)
+        }
+
+        /// Creates a proposal and counts it as confirmed by the proposer.
+        #[ink(message)]
+        pub fn propose(&mut self, action: AdminAction) -> Result<ProposalId> {
+            let caller = self.env().caller();
+            self.ensure_signer_impl(&caller)?;
+
+            let proposal_id = self.next_proposal_id;
+            self.next_proposal_id = proposal_id.checked_add(1).ok_or(Error::Overflow)?;
+            let expires_at = self.env().block_timestamp()
+                .checked_add(PROPOSAL_LIFETIME)
+                .ok_or(Error::Overflow)?;
+
+            self.proposals.insert(proposal_id, &Proposal {
+                action: action.clone(),
+                proposer: caller,
+                confirmations: ink_prelude::vec![caller],
+                expires_at,
+                executed: false
+            });
+            self.env().emit_event(Proposed {
+                proposal_id,
+                proposer: caller,
+                action,
+                expires_at
+            });
+            self.env().emit_event(Confirmed {
+                proposal_id,
+                signer: caller
+            });
+            Ok(proposal_id)
+        }
+
+        #[ink(message)]
+        pub fn confirm(&mut self, proposal_id: ProposalId) -> Result<()> {
+            let caller = self.env().caller();
+            self.ensure_signer_impl(&caller)?;
+            let mut proposal = self.pending_proposal_impl(proposal_id)?;
+            if proposal.confirmations.contains(&caller) {
+                return Err(Error::AlreadyConfirmed)
+            }
+
+            proposal.confirmations.push(caller);
+            self.proposals.insert(proposal_id, &proposal);
+            self.env().emit_event(Confirmed {
+                proposal_id,
+                signer: caller
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn revoke_confirmation(&mut self, proposal_id: ProposalId) -> Result<()> {
+            let caller = self.env().caller();
+            self.ensure_signer_impl(&caller)?;
+            let mut proposal = self.pending_proposal_impl(proposal_id)?;
+            let idx = proposal.confirmations
+                .iter()
+                .position(|a| *a == caller)
+                .ok_or(Error::NotConfirmed)?;
+
+            proposal.confirmations.swap_remove(idx);
+            self.proposals.insert(proposal_id, &proposal);
+            self.env().emit_event(ConfirmationRevoked {
+                proposal_id,
+                signer: caller
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn execute(&mut self, proposal_id: ProposalId) -> Result<()> {
+            let caller = self.env().caller();
+            self.ensure_signer_impl(&caller)?;
+            let mut proposal = self.pending_proposal_impl(proposal_id)?;
+            // confirmations of removed signers do not count
+            let confirmed = proposal.confirmations
+                .iter()
+                .filter(|a| self.signers.contains(a))
+                .count();
+            if confirmed < self.threshold as usize {
+                return Err(Error::ThresholdNotReached)
+            }
+
+            self.execute_action_impl(&proposal.action)?;
+            proposal.executed = true;
+            self.proposals.insert(proposal_id, &proposal);
+            self.env().emit_event(Executed {
+                proposal_id,
+                executor: caller
+            });
+            Ok(())
+        }
+
+        #[inline]
+        fn ensure_signer_impl(&self, account: &AccountId) -> Result<()> {
+            if !self.signers.contains(account) {
+                return Err(Error::NotSigner)
+            }
+            Ok(())
+        }
+
+        fn pending_proposal_impl(&self, proposal_id: ProposalId) -> Result<Proposal> {
+            let proposal = self.proposals.get(proposal_id).ok_or(Error::ProposalNotFound)?;
+            if proposal.executed {
+                return Err(Error::ProposalAlreadyExecuted)
+            }
+            if self.env().block_timestamp() > proposal.expires_at {
+                return Err(Error::ProposalExpired)
+            }
+            Ok(proposal)
+        }
+
+        fn execute_action_impl(&mut self, action: &AdminAction) -> Result<()> {
+            match action {
+                AdminAction::Mint { to, value } => {
+                    self.mint_impl(to, *value)
+                },
+                AdminAction::Burn { from, value } => {
+                    self.burn_impl(from, *value)
+                },
+                AdminAction::ChangeSigners(signers) => {
+                    if signers.is_empty() || self.threshold as usize > signers.len() {
+                        return Err(Error::InvalidSigners)
+                    }
+                    for (i, s) in signers.iter().enumerate() {
+                        if signers[i + 1..].contains(s) {
+                            return Err(Error::InvalidSigners)
+                        }
+                    }
+                    self.signers = signers.clone();
+                    Ok(())
+                },
+                AdminAction::ChangeThreshold(threshold) => {
+                    if *threshold == 0 || *threshold as usize > self.signers.len() {
+                        return Err(Error::InvalidThreshold)
+                    }
+                    self.threshold = *threshold;
+                    Ok(())
+                },
+                AdminAction::ChangeRebaser(rebaser) => {
+                    if self.rebaser.is_none() {
+                        return Err(Error::RebasingDisabled)
+                    }
+                    self.rebaser = Some(*rebaser);
+                    Ok(())
+                },
+                AdminAction::SetDefaultOutflowLimit(limit) => {
+                    Self::ensure_valid_limit_impl(limit)?;
+                    self.default_outflow_limit = *limit;
+                    Ok(())
+                },
+                AdminAction::SetOutflowLimit { account, limit } => {
+                    Self::ensure_valid_limit_impl(limit)?;
+                    match limit {
+                        Some(l) => self.outflow_limits.insert(account, l),
+                        None => self.outflow_limits.remove(account)
+                    }
+                    Ok(())
+                }
+            }
+        }
+
+        fn mint_impl(&mut self, to: &AccountId, value: Balance) -> Result<()> {
+            let shares = self.to_shares_impl(value)?;
+            let new_supply = self.total_supply.checked_add(value).ok_or(Error::Overflow)?;
+            let new_total_shares = self.total_shares.checked_add(shares).ok_or(Error::Overflow)?;
+            let new_shares = self.shares_of_impl(to)
+                .checked_add(shares)
+                .ok_or(Error::Overflow)?;
+            self.total_supply = new_supply;
+            self.total_shares = new_total_shares;
+            self.balances.insert(to, &new_shares);
+            self.env().emit_event(Transfer {
+                from: None,
+                to: Some(*to),
+                value
+            });
+            Ok(())
+        }
+
+        fn burn_impl(&mut self, from: &AccountId, value: Balance) -> Result<()> {
+            let shares = self.to_shares_impl(value)?;
+            let src_shares = self.shares_of_impl(from);
+            if src_shares < shares {
+                return Err(Error::InsufficientBalance)
+            }
+            self.balances.insert(from, &(src_shares - shares));
+            self.total_shares -= shares;
+            self.total_supply = self.total_supply.saturating_sub(value);
+            self.env().emit_event(Transfer {
+                from: Some(*from),
+                to: None,
+                value
+            });
+            Ok(())
+        }
+
+        // rebasing
+        #[ink(message)]
+        pub fn rebaser(&self) -> Option<AccountId> {
+            self.rebaser
+        }
+
+        #[ink(message)]
+        pub fn shares_per_unit(&self) -> Balance {
+            self.shares_per_unit
+        }
+
+        #[ink(message)]
+        pub fn total_shares(&self) -> Balance {
+            self.total_shares
+        }
+
+        /// Moves total supply by `supply_delta`, scaling every balance proportionally.
+        /// Returns the new total supply, which can be slightly above the requested one.
+        #[ink(message)]
+        pub fn rebase(&mut self, supply_delta: i128) -> Result<Balance> {
+            let rebaser = self.rebaser.ok_or(Error::RebasingDisabled)?;
+            let caller = self.env().caller();
+            if caller != rebaser {
+                return Err(Error::NotRebaser)
+            }
+
+            let delta = supply_delta.unsigned_abs();
+            let new_supply = if supply_delta < 0 {
+                self.total_supply.checked_sub(delta)
+            } else {
+                self.total_supply.checked_add(delta)
+            }.ok_or(Error::InvalidSupply)?;
+            // every unit needs at least one share behind it
+            if new_supply == 0 || new_supply > self.total_shares {
+                return Err(Error::InvalidSupply)
+            }
+
+            // shares_per_unit is truncated, so the supply the shares
+            // really back can be above the requested one
+            let shares_per_unit = self.total_shares / new_supply;
+            let backed_supply = self.total_shares / shares_per_unit;
+            if backed_supply - new_supply > new_supply / REBASE_TOLERANCE {
+                return Err(Error::RebaseTooImprecise)
+            }
+
+            self.shares_per_unit = shares_per_unit;
+            self.total_supply = backed_supply;
+            self.env().emit_event(Rebase {
+                rebaser: caller,
+                total_supply: backed_supply,
+                shares_per_unit
+            });
+            Ok(backed_supply)
+        }
+
+        // rate limits
+        #[ink(message)]
+        pub fn outflow_limit(&self, account: AccountId) -> Option<OutflowLimit> {
+            self.outflow_limit_impl(&account)
+        }
+
+        /// How much `account` can still send right now, `Balance::MAX` if unlimited.
+        #[ink(message)]
+        pub fn remaining_outflow(&self, account: AccountId) -> Balance {
+            match self.outflow_limit_impl(&account) {
+                Some(limit) => limit.amount.saturating_sub(self.current_outflow_impl(&account, &limit)),
+                None => Balance::MAX
+            }
+        }
+
+        #[inline]
+        fn outflow_limit_impl(&self, account: &AccountId) -> Option<OutflowLimit> {
+            // escrowed funds (streams) are never limited
+            if *account == self.env().account_id() {
+                return None
+            }
+            self.outflow_limits.get(account).or(self.default_outflow_limit)
+        }
+
+        fn current_outflow_impl(&self, account: &AccountId, limit: &OutflowLimit) -> Balance {
+            let (outflow, updated_at) = self.outflows.get(account).unwrap_or_default();
+            let elapsed = self.env().block_timestamp().saturating_sub(updated_at);
+            if elapsed >= limit.window {
+                return 0
+            }
+            let drained = limit.amount
+                .saturating_mul(elapsed as Balance)
+                / limit.window as Balance;
+            outflow.saturating_sub(drained)
+        }
+
+        fn record_outflow_impl(&mut self, account: &AccountId, value: Balance) -> Result<()> {
+            let limit = match self.outflow_limit_impl(account) {
+                Some(l) => l,
+                None => return Ok(())
+            };
+            let outflow = self.current_outflow_impl(account, &limit)
+                .checked_add(value)
+                .ok_or(Error::RateLimited)?;
+            if outflow > limit.amount {
+                return Err(Error::RateLimited)
+            }
+            self.outflows.insert(account, &(outflow, self.env().block_timestamp()));
+            Ok(())
+        }
+
+        #[inline]
+        fn ensure_valid_limit_impl(limit: &Option<OutflowLimit>) -> Result<()> {
+            if let Some(l) = limit {
+                if l.window == 0 {
+                    return Err(Error::InvalidOutflowLimit)
+                }
+            }
+            Ok(())
+        }
+
+        // holds
+        #[ink(message)]
+        pub fn authorize_hold_operator(&mut self, operator: AccountId, authorized: bool
+        ) -> Result<()> {
+            let owner = self.env().caller();
+            if owner == operator {
+                return Err(Error::AttemptingSelfAllowance)
+            }
+
+            if authorized {
+                self.hold_operators.insert((&owner, &operator), &());
+            } else {
+                self.hold_operators.remove((&owner, &operator));
+            }
+            self.env().emit_event(HoldOperatorAuthorization {
+                owner,
+                operator,
+                authorized
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_hold_operator(&self, owner: AccountId, operator: AccountId) -> bool {
+            self.hold_operators.contains((&owner, &operator))
+        }
+
+        #[ink(message)]
+        pub fn get_hold(&self, operator: AccountId, hold_id: HoldId) -> Option<Hold> {
+            self.holds.get((&operator, &hold_id))
+        }
+
+        #[ink(message)]
+        pub fn balance_on_hold(&self, owner: AccountId) -> Balance {
+            self.balance_on_hold_impl(&owner)
+        }
+
+        #[inline]
+        fn balance_on_hold_impl(&self, owner: &AccountId) -> Balance {
+            self.held_balances.get(owner).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn spendable_balance(&self, owner: AccountId) -> Balance {
+            self.balance_of_impl(&owner).saturating_sub(self.balance_on_hold_impl(&owner))
+        }
+
+        // held amounts are fixed, so not in rebasing mode.
+        #[ink(message)]
+        pub fn hold(
+            &mut self,
+            owner: AccountId,
+            amount: Balance,
+            hold_id: HoldId,
+            expires_at: Timestamp
+        ) -> Result<()> {
+            if self.rebaser.is_some() {
+                return Err(Error::UnsupportedWhenRebasing)
+            }
+            let operator = self.env().caller();
+            if !self.hold_operators.contains((&owner, &operator)) {
+                return Err(Error::NotHoldOperator)
+            }
+            if self.holds.contains((&operator, &hold_id)) {
+                return Err(Error::HoldAlreadyExists)
+            }
+            if expires_at <= self.env().block_timestamp() {
+                return Err(Error::InvalidHoldExpiry)
+            }
+            let held = self.balance_on_hold_impl(&owner);
+            if self.balance_of_impl(&owner).saturating_sub(held) < amount {
+                return Err(Error::InsufficientBalance)
+            }
+
+            self.holds.insert((&operator, &hold_id), &Hold {
+                owner,
+                operator,
+                amount,
+                expires_at
+            });
+            self.held_balances.insert(owner, &(held + amount));
+            self.env().emit_event(HoldCreated {
+                hold_id,
+                owner,
+                operator,
+                amount,
+                expires_at
+            });
+            Ok(())
+        }
+
+        /// Gives held funds back to the owner.
+        /// The operator can release any time, anyone else only after expiry.
+        #[ink(message)]
+        pub fn release_hold(&mut self, operator: AccountId, hold_id: HoldId) -> Result<()> {
+            let hold = self.holds.get((&operator, &hold_id)).ok_or(Error::HoldNotFound)?;
+            if self.env().caller() != operator
+                && self.env().block_timestamp() < hold.expires_at {
+                return Err(Error::NotHoldOperator)
+            }
+
+            self.remove_hold_impl(hold_id, &hold);
+            self.env().emit_event(HoldReleased {
+                hold_id,
+                owner: hold.owner,
+                operator,
+                amount: hold.amount
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn execute_hold(&mut self, hold_id: HoldId, to: AccountId) -> Result<()> {
+            let operator = self.env().caller();
+            let hold = self.holds.get((&operator, &hold_id)).ok_or(Error::HoldNotFound)?;
+            if self.env().block_timestamp() >= hold.expires_at {
+                return Err(Error::HoldExpired)
+            }
+            if hold.owner == to {
+                return Err(Error::AttemptingSelfTransfer)
+            }
+
+            self.transfer_from_to_impl(&hold.owner, &to, hold.amount, hold.amount)?;
+            self.remove_hold_impl(hold_id, &hold);
+            self.env().emit_event(HoldExecuted {
+                hold_id,
+                owner: hold.owner,
+                to,
+                operator,
+                amount: hold.amount
+            });
+            Ok(())
+        }
+
+        fn remove_hold_impl(&mut self, hold_id: HoldId, hold: &Hold) {
+            self.holds.remove((&hold.operator, &hold_id));
+            let held = self.balance_on_hold_impl(&hold.owner) - hold.amount;
+            if held == 0 {
+                self.held_balances.remove(hold.owner);
+            } else {
+                self.held_balances.insert(hold.owner, &held);
+            }
+        }
+
+        // (sender, recipient)
+        fn stream_balances_impl(&self, stream: &Stream) -> (Balance, Balance) {
+            let now = self.env().block_timestamp();
+            let streamed = if now <= stream.start {
+                0
+            } else if now >= stream.stop {
+                stream.deposit
+            } else {
+                let elapsed = (now - stream.start) as Balance;
+                let duration = (stream.stop - stream.start) as Balance;
+                // split the deposit so the product cannot overflow
+                stream.deposit / duration * elapsed
+                    + stream.deposit % duration * elapsed / duration
+            };
+            (stream.deposit - streamed, streamed - stream.withdrawn)
+        }
+
+        fn remove_stream_impl(&mut self, stream_id: StreamId, stream: &Stream) {
+            self.streams.remove(stream_id);
+            Self::remove_stream_id_impl(&mut self.sender_streams, &stream.sender, stream_id);
+            Self::remove_stream_id_impl(&mut self.recipient_streams, &stream.recipient, stream_id);
+        }
+
+        fn push_stream_id_impl(
+            list: &mut Mapping<AccountId, StreamIdList>,
+            owner: &AccountId,
+            stream_id: StreamId
+        ) {
+            let mut v = list.get(owner).unwrap_or_default();
+            v.push(stream_id);
+            list.insert(owner, &v);
+        }
+
+        fn remove_stream_id_impl(
+            list: &mut Mapping<AccountId, StreamIdList>,
+            owner: &AccountId,
+            stream_id: StreamId
+        ) {
+            if let Some(mut v) = list.get(owner) {
+                if let Some(idx) = v.iter().position(|&i| i == stream_id) {
+                    v.swap_remove(idx);
+                }
+                if v.is_empty() {
+                    list.remove(owner);
+                } else {
+                    list.insert(owner, &v);
+                }
+            }
+        }
     }
 
     /// Unit tests in Rust are normally defined within such a `#[cfg(test)]`
@@ -185,15 +1168,295 @@ mod erc20 {
     #[cfg(test)]
     mod tests {
         /// Imports all the definitions from the outer scope so we can use them here.
-        //use super::*;
+        use super::*;
 
         /// Imports `ink_lang` so we can use `#[ink::test]`.
         use ink_lang as ink;
 
+        type Env = ink_env::DefaultEnvironment;
+
+        fn advance_to(timestamp: Timestamp) {
+            while ink_env::block_timestamp::<Env>() < timestamp {
+                ink_env::test::advance_block::<Env>();
+            }
+        }
+
         /// We test if the default constructor does its job.
         #[ink::test]
         fn something_works() {
             
         }
+
+        #[ink::test]
+        fn large_stream_settles_midway() {
+            let accounts = ink_env::test::default_accounts::<Env>();
+            ink_env::test::set_callee::<Env>(accounts.frank);
+            let mut erc20 = Erc20::default();
+            let year: Timestamp = 365 * 24 * 60 * 60 * 1000;
+            // deposit * elapsed does not fit in a Balance
+            let per_millisecond: Balance = 1_000_000_000_000_000_000_000_000_000;
+            let deposit = per_millisecond * year as Balance;
+            let id = erc20.create_stream(accounts.bob, deposit, 0, year).unwrap();
+
+            advance_to(60);
+            let streamed = erc20.balance_of_stream(id, accounts.bob).unwrap();
+            assert_eq!(streamed, per_millisecond * 60);
+            ink_env::test::set_caller::<Env>(accounts.bob);
+            assert_eq!(erc20.cancel_stream(id), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), streamed);
+            assert_eq!(erc20.balance_of(accounts.frank), 0);
+        }
+
+        #[ink::test]
+        fn rebase_keeps_supply_backed_by_shares() {
+            let accounts = ink_env::test::default_accounts::<Env>();
+            let mut erc20 = Erc20::new_rebasing(1_000, accounts.alice);
+            assert_eq!(erc20.total_shares(), 1_000_000_000_000);
+
+            // 1e12 shares / 1.5e11 units would truncate to 6 shares per unit
+            let delta = 150_000_000_000 - 1_000;
+            assert_eq!(erc20.rebase(delta), Err(Error::RebaseTooImprecise));
+            assert_eq!(erc20.total_supply(), 1_000);
+
+            assert_eq!(erc20.rebase(1_000), Ok(2_000));
+            assert_eq!(erc20.balance_of(accounts.alice), 2_000);
+            // 999 shares per unit, shares back 1_001_001_001 units
+            assert_eq!(erc20.rebase(1_001_000_000 - 2_000), Ok(1_001_001_001));
+            assert_eq!(erc20.balance_of(accounts.alice), erc20.total_supply());
+        }
+
+        #[ink::test]
+        fn rebasing_mode_has_no_streams_or_holds() {
+            let accounts = ink_env::test::default_accounts::<Env>();
+            ink_env::test::set_callee::<Env>(accounts.frank);
+            let mut erc20 = Erc20::new_rebasing(1_000, accounts.alice);
+            assert_eq!(
+                erc20.create_stream(accounts.bob, 100, 0, 60),
+                Err(Error::UnsupportedWhenRebasing)
+            );
+
+            erc20.authorize_hold_operator(accounts.bob, true).unwrap();
+            ink_env::test::set_caller::<Env>(accounts.bob);
+            assert_eq!(
+                erc20.hold(accounts.alice, 100, Hash::from([1; 32]), 60),
+                Err(Error::UnsupportedWhenRebasing)
+            );
+        }
+
+        fn multisig_of_three(erc20: &mut Erc20) {
+            let accounts = ink_env::test::default_accounts::<Env>();
+            let signers = ink_prelude::vec![accounts.alice, accounts.bob, accounts.charlie];
+            let id = erc20.propose(AdminAction::ChangeSigners(signers)).unwrap();
+            assert_eq!(erc20.execute(id), Ok(()));
+            let id = erc20.propose(AdminAction::ChangeThreshold(2)).unwrap();
+            assert_eq!(erc20.execute(id), Ok(()));
+        }
+
+        #[ink::test]
+        fn multisig_needs_threshold_confirmations() {
+            let accounts = ink_env::test::default_accounts::<Env>();
+            let mut erc20 = Erc20::new(1_000);
+            multisig_of_three(&mut erc20);
+            assert_eq!(erc20.threshold(), 2);
+
+            ink_env::test::set_caller::<Env>(accounts.django);
+            assert_eq!(
+                erc20.propose(AdminAction::Mint { to: accounts.django, value: 500 }),
+                Err(Error::NotSigner)
+            );
+
+            ink_env::test::set_caller::<Env>(accounts.alice);
+            let id = erc20.propose(AdminAction::Mint { to: accounts.django, value: 500 }).unwrap();
+            assert_eq!(erc20.confirm(id), Err(Error::AlreadyConfirmed));
+            assert_eq!(erc20.execute(id), Err(Error::ThresholdNotReached));
+
+            ink_env::test::set_caller::<Env>(accounts.bob);
+            assert_eq!(erc20.confirm(id), Ok(()));
+            assert_eq!(erc20.revoke_confirmation(id), Ok(()));
+            assert_eq!(erc20.revoke_confirmation(id), Err(Error::NotConfirmed));
+            assert_eq!(erc20.execute(id), Err(Error::ThresholdNotReached));
+            assert_eq!(erc20.balance_of(accounts.django), 0);
+
+            ink_env::test::set_caller::<Env>(accounts.charlie);
+            assert_eq!(erc20.confirm(id), Ok(()));
+            assert_eq!(erc20.execute(id), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.django), 500);
+            assert_eq!(erc20.total_supply(), 1_500);
+
+            assert_eq!(erc20.execute(id), Err(Error::ProposalAlreadyExecuted));
+            ink_env::test::set_caller::<Env>(accounts.bob);
+            assert_eq!(erc20.confirm(id), Err(Error::ProposalAlreadyExecuted));
+            assert_eq!(erc20.balance_of(accounts.django), 500);
+        }
+
+        #[ink::test]
+        fn multisig_ignores_removed_signers() {
+            let accounts = ink_env::test::default_accounts::<Env>();
+            let mut erc20 = Erc20::new(1_000);
+            multisig_of_three(&mut erc20);
+
+            let burn = erc20.propose(AdminAction::Burn { from: accounts.alice, value: 100 }).unwrap();
+            ink_env::test::set_caller::<Env>(accounts.bob);
+            assert_eq!(erc20.confirm(burn), Ok(()));
+
+            ink_env::test::set_caller::<Env>(accounts.alice);
+            let signers = ink_prelude::vec![accounts.alice, accounts.charlie];
+            let change = erc20.propose(AdminAction::ChangeSigners(signers.clone())).unwrap();
+            ink_env::test::set_caller::<Env>(accounts.charlie);
+            assert_eq!(erc20.confirm(change), Ok(()));
+            assert_eq!(erc20.execute(change), Ok(()));
+            assert_eq!(erc20.signers(), signers);
+
+            // bob's confirmation no longer counts
+            assert_eq!(erc20.execute(burn), Err(Error::ThresholdNotReached));
+            ink_env::test::set_caller::<Env>(accounts.bob);
+            assert_eq!(erc20.execute(burn), Err(Error::NotSigner));
+
+            ink_env::test::set_caller::<Env>(accounts.charlie);
+            assert_eq!(erc20.confirm(burn), Ok(()));
+            assert_eq!(erc20.execute(burn), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.alice), 900);
+        }
+
+        #[ink::test]
+        fn multisig_validates_signers_and_threshold() {
+            let accounts = ink_env::test::default_accounts::<Env>();
+            let mut erc20 = Erc20::new(1_000);
+            multisig_of_three(&mut erc20);
+
+            let invalid = [
+                (AdminAction::ChangeSigners(ink_prelude::vec![]), Error::InvalidSigners),
+                // shorter than the threshold of 2
+                (AdminAction::ChangeSigners(ink_prelude::vec![accounts.alice]), Error::InvalidSigners),
+                (
+                    AdminAction::ChangeSigners(ink_prelude::vec![accounts.alice, accounts.alice]),
+                    Error::InvalidSigners
+                ),
+                (AdminAction::ChangeThreshold(0), Error::InvalidThreshold),
+                (AdminAction::ChangeThreshold(4), Error::InvalidThreshold),
+            ];
+            for (action, error) in invalid {
+                ink_env::test::set_caller::<Env>(accounts.alice);
+                let id = erc20.propose(action).unwrap();
+                ink_env::test::set_caller::<Env>(accounts.bob);
+                assert_eq!(erc20.confirm(id), Ok(()));
+                assert_eq!(erc20.execute(id), Err(error));
+            }
+            assert_eq!(
+                erc20.signers(),
+                ink_prelude::vec![accounts.alice, accounts.bob, accounts.charlie]
+            );
+            assert_eq!(erc20.threshold(), 2);
+        }
+
+        #[ink::test]
+        fn multisig_proposal_expires() {
+            let accounts = ink_env::test::default_accounts::<Env>();
+            let mut erc20 = Erc20::new(1_000);
+            let id = erc20.propose(AdminAction::Mint { to: accounts.bob, value: 500 }).unwrap();
+            let proposal = erc20.get_proposal(id).unwrap();
+            assert_eq!(proposal.expires_at, PROPOSAL_LIFETIME);
+
+            // a week of 6ms blocks is too many to advance, shorten the lifetime instead
+            let expires_at = ink_env::block_timestamp::<Env>();
+            erc20.proposals.insert(id, &Proposal { expires_at, ..proposal });
+            ink_env::test::advance_block::<Env>();
+            assert_eq!(erc20.confirm(id), Err(Error::ProposalExpired));
+            assert_eq!(erc20.execute(id), Err(Error::ProposalExpired));
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+            assert_eq!(erc20.get_proposal(id + 1), None);
+            assert_eq!(erc20.execute(id + 1), Err(Error::ProposalNotFound));
+        }
+
+        #[ink::test]
+        fn outflow_drains_over_window() {
+            let accounts = ink_env::test::default_accounts::<Env>();
+            ink_env::test::set_callee::<Env>(accounts.frank);
+            let mut erc20 = Erc20::new(10_000);
+            let limit = OutflowLimit { amount: 600, window: 600 };
+            let id = erc20.propose(AdminAction::SetDefaultOutflowLimit(
+                Some(OutflowLimit { window: 0, ..limit })
+            )).unwrap();
+            assert_eq!(erc20.execute(id), Err(Error::InvalidOutflowLimit));
+            let id = erc20.propose(AdminAction::SetDefaultOutflowLimit(Some(limit))).unwrap();
+            assert_eq!(erc20.execute(id), Ok(()));
+            assert_eq!(erc20.outflow_limit(accounts.alice), Some(limit));
+
+            assert_eq!(erc20.transfer(accounts.bob, 600), Ok(()));
+            assert_eq!(erc20.remaining_outflow(accounts.alice), 0);
+            assert_eq!(erc20.transfer(accounts.bob, 1), Err(Error::RateLimited));
+
+            // half the window drains half the limit
+            advance_to(300);
+            assert_eq!(erc20.remaining_outflow(accounts.alice), 300);
+            assert_eq!(erc20.transfer(accounts.bob, 301), Err(Error::RateLimited));
+            assert_eq!(erc20.transfer(accounts.bob, 300), Ok(()));
+            assert_eq!(erc20.transfer(accounts.bob, 1), Err(Error::RateLimited));
+
+            // a full window after the last transfer everything has drained
+            advance_to(900);
+            assert_eq!(erc20.remaining_outflow(accounts.alice), 600);
+            assert_eq!(erc20.transfer(accounts.bob, 600), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 1_500);
+            assert_eq!(erc20.remaining_outflow(accounts.bob), 600);
+        }
+
+        #[ink::test]
+        fn held_funds_are_only_spent_by_their_hold() {
+            let accounts = ink_env::test::default_accounts::<Env>();
+            ink_env::test::set_callee::<Env>(accounts.frank);
+            let mut erc20 = Erc20::new(1_000);
+            let hold_id = Hash::from([1; 32]);
+            erc20.authorize_hold_operator(accounts.bob, true).unwrap();
+            erc20.authorize_hold_operator(accounts.charlie, true).unwrap();
+            erc20.approve(accounts.eve, 1_000).unwrap();
+
+            // both operators may pick the same id
+            ink_env::test::set_caller::<Env>(accounts.bob);
+            assert_eq!(erc20.hold(accounts.alice, 600, hold_id, 1_000), Ok(()));
+            ink_env::test::set_caller::<Env>(accounts.charlie);
+            assert_eq!(erc20.hold(accounts.alice, 300, hold_id, 1_000), Ok(()));
+            assert_eq!(
+                erc20.hold(accounts.alice, 100, hold_id, 1_000),
+                Err(Error::HoldAlreadyExists)
+            );
+            assert_eq!(erc20.balance_on_hold(accounts.alice), 900);
+            assert_eq!(erc20.spendable_balance(accounts.alice), 100);
+
+            ink_env::test::set_caller::<Env>(accounts.alice);
+            assert_eq!(erc20.transfer(accounts.django, 101), Err(Error::InsufficientBalance));
+            assert_eq!(
+                erc20.create_stream(accounts.django, 101, 10, 100),
+                Err(Error::InsufficientBalance)
+            );
+            ink_env::test::set_caller::<Env>(accounts.eve);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.eve, 101),
+                Err(Error::InsufficientBalance)
+            );
+
+            ink_env::test::set_caller::<Env>(accounts.django);
+            assert_eq!(erc20.execute_hold(hold_id, accounts.django), Err(Error::HoldNotFound));
+            assert_eq!(erc20.release_hold(accounts.charlie, hold_id), Err(Error::NotHoldOperator));
+
+            ink_env::test::set_caller::<Env>(accounts.bob);
+            assert_eq!(erc20.execute_hold(hold_id, accounts.django), Ok(()));
+            assert_eq!(erc20.execute_hold(hold_id, accounts.django), Err(Error::HoldNotFound));
+            assert_eq!(erc20.balance_of(accounts.django), 600);
+            assert_eq!(erc20.get_hold(accounts.bob, hold_id), None);
+            assert_eq!(erc20.get_hold(accounts.charlie, hold_id).map(|h| h.amount), Some(300));
+
+            // charlie's hold is still out of reach
+            assert_eq!(erc20.balance_on_hold(accounts.alice), 300);
+            assert_eq!(erc20.spendable_balance(accounts.alice), 100);
+            ink_env::test::set_caller::<Env>(accounts.alice);
+            assert_eq!(erc20.transfer(accounts.django, 101), Err(Error::InsufficientBalance));
+
+            ink_env::test::set_caller::<Env>(accounts.charlie);
+            assert_eq!(erc20.release_hold(accounts.charlie, hold_id), Ok(()));
+            assert_eq!(erc20.balance_on_hold(accounts.alice), 0);
+            ink_env::test::set_caller::<Env>(accounts.alice);
+            assert_eq!(erc20.transfer(accounts.django, 400), Ok(()));
+        }
     }
 }
@@ -2,6 +2,28 @@
 
 use ink_lang as ink;
 
+mod erc721_interfaces {
+    use super::*;
+    use ink_env::{ AccountId, Hash };
+    use ink_prelude::vec::Vec;
+
+    /// Contracts receiving tokens through `safe_transfer_from` implement this.
+    #[ink::trait_definition]
+    pub trait Erc721Receiver {
+        /// Returns the selector bytes `[0x15, 0x0b, 0x7a, 0x02]` to accept the token.
+        #[ink(message, selector = 0x150B7A02)]
+        fn on_erc721_received(
+            &mut self,
+            operator: AccountId,
+            from: AccountId,
+            id: Hash,
+            data: Vec<u8>
+        ) -> Vec<u8>;
+    }
+}
+
+pub use erc721_interfaces::Erc721Receiver;
+
 #[ink::contract]
 mod erc721 {
 
@@ -28,7 +50,64 @@ mod erc721 {
 
         NotEnoughFee,
         FounderOnly,
-        NotEnoughBalance
+        NotEnoughBalance,
+
+        TransferDenied,
+        InvalidRoyaltyRate,
+
+        CommitmentAlreadyExists,
+        CommitmentNotFound,
+        RevealTooEarly,
+        CommitmentExpired,
+        CommitmentNotExpired,
+        CommitRevealRequired,
+        CommitRevealDisabled,
+
+        TokenKeyNotSet,
+        InvalidSignature,
+
+        NotListed,
+        NotSeller,
+        InvalidListing,
+        ListingExpired,
+
+        AuctionNotFound,
+        InvalidAuction,
+        AuctionEnded,
+        AuctionNotEnded,
+        BidTooLow,
+        NoPendingReturns,
+
+        InvalidUserExpiry,
+
+        TokenLocked,
+
+        NotVoucherRecipient,
+        VoucherExpired,
+        VoucherAlreadyRedeemed,
+
+        DuplicateToken,
+
+        InvalidMintPhase,
+        MintPhaseNotFound,
+        NoActiveMintPhase,
+        InvalidMerkleProof,
+        PhaseLimitReached,
+        MintPhaseActive,
+
+        MaxSupplyReached,
+        WalletMintLimitReached,
+        InvalidMaxSupply,
+
+        TokenBurned,
+
+        NotAttributeUpdater,
+        AttributeTooLarge,
+        TooManyAttributes,
+
+        InvalidPayees,
+        NotPayee,
+        NoPaymentDue
     }
 
     // https://ink.substrate.io/faq#how-do-i-hash-a-value
@@ -42,10 +121,50 @@ mod erc721 {
     type InternalResult = Result<(), ()>;
     type ApprovalPair = (/*owner*/AccountId, /*operator*/AccountId);
     type ApprovedAccounts = ink_prelude::vec::Vec<AccountId>;
-
-    const MINT_FEE: Balance = 100;
-    const BURN_FEE: Balance = 100;
-    const TRANSFER_FEE: Balance = 100;
+    type TokenUri = ink_prelude::string::String;
+    type CollectionName = ink_prelude::string::String;
+    type CollectionSymbol = ink_prelude::string::String;
+    type TokenIdList = ink_prelude::vec::Vec<TokenId>;
+    type BytesVec = ink_prelude::vec::Vec<u8>;
+    // basis points, 10_000 is 100%.
+    type RoyaltyRate = u16;
+    // blake2x256 of scale encoded (gen, phrase, salt)
+    type MintCommitment = Hash;
+    type MintSalt = Hash;
+    // compressed ecdsa public key and recoverable signature
+    type TokenPublicKey = [u8; 33];
+    type TokenSignature = [u8; 65];
+    type TokenGenSignaturePairs = ink_prelude::vec::Vec<(TokenGen, TokenSignature)>;
+    type TokenNonce = u64;
+    type ListingList = ink_prelude::vec::Vec<(TokenId, Listing)>;
+    // blake2x256 of scale encoded (contract, voucher), what minter keys sign.
+    type VoucherHash = Hash;
+    type MintPhaseId = u32;
+    // sibling hashes from the leaf up to the root.
+    type MerkleProof = ink_prelude::vec::Vec<Hash>;
+    type AttributeKey = ink_prelude::string::String;
+    type AttributeValue = ink_prelude::string::String;
+    type AttributeKeyList = ink_prelude::vec::Vec<AttributeKey>;
+    type AttributeList = ink_prelude::vec::Vec<(AttributeKey, AttributeValue)>;
+    type PayeeList = ink_prelude::vec::Vec<AccountId>;
+    type PayeeShares = ink_prelude::vec::Vec<(AccountId, u32)>;
+
+    const DEFAULT_MINT_FEE: Balance = 100;
+    const DEFAULT_BURN_FEE: Balance = 100;
+    const DEFAULT_TRANSFER_FEE: Balance = 100;
+
+    const ROYALTY_DENOMINATOR: Balance = 10_000;
+
+    // in blocks, reveal is allowed in [commit + MIN_REVEAL_DELAY, commit + COMMITMENT_LIFETIME].
+    const MIN_REVEAL_DELAY: BlockNumber = 2;
+    const COMMITMENT_LIFETIME: BlockNumber = 14_400;
+
+    // in bytes.
+    const MAX_ATTRIBUTE_KEY_LEN: usize = 32;
+    const MAX_ATTRIBUTE_VALUE_LEN: usize = 256;
+    const MAX_ATTRIBUTES_PER_TOKEN: usize = 32;
+
+    const ON_ERC721_RECEIVED_SELECTOR: [u8; 4] = [0x15, 0x0b, 0x7a, 0x02];
 
     #[derive(
         Debug, PartialEq, Eq, Clone, Copy,
@@ -56,6 +175,122 @@ mod erc721 {
         All
     }
 
+    #[derive(
+        Debug, PartialEq, Eq, Clone, Copy,
+        Encode, Decode, SpreadLayout, PackedLayout
+    )]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct Royalty {
+        pub receiver: AccountId,
+        pub rate: RoyaltyRate
+    }
+
+    #[derive(
+        Debug, Default, PartialEq, Eq, Clone, Copy,
+        Encode, Decode, SpreadLayout, PackedLayout, SpreadAllocate
+    )]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    pub struct Fees {
+        pub mint: Balance,
+        pub burn: Balance,
+        pub transfer: Balance
+    }
+
+    #[derive(
+        Debug, PartialEq, Eq, Clone, Copy,
+        Encode, Decode, SpreadLayout, PackedLayout
+    )]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct PendingMint {
+        committer: AccountId,
+        deposit: Balance,
+        committed_at: BlockNumber
+    }
+
+    #[derive(
+        Debug, PartialEq, Eq, Clone, Copy,
+        Encode, Decode, SpreadLayout, PackedLayout
+    )]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct Listing {
+        seller: AccountId,
+        price: Balance,
+        expires_at: Timestamp
+    }
+
+    #[derive(
+        Debug, PartialEq, Eq, Clone, Copy,
+        Encode, Decode, SpreadLayout, PackedLayout
+    )]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum AuctionKind {
+        English { reserve: Balance },
+        // price falls linearly from start_price at start to end_price at end
+        Dutch { start_price: Balance, end_price: Balance }
+    }
+
+    // auctioned token is held by the contract account until settled.
+    #[derive(
+        Debug, PartialEq, Eq, Clone, Copy,
+        Encode, Decode, SpreadLayout, PackedLayout
+    )]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct Auction {
+        seller: AccountId,
+        kind: AuctionKind,
+        start: Timestamp,
+        end: Timestamp,
+        highest_bidder: Option<AccountId>,
+        highest_bid: Balance
+    }
+
+    // mint_with_proof is open in [start, end), the earliest added phase wins
+    // when several are open. While a phase is open the other public mints
+    // are closed, outside phases they are open at the mint fee and
+    // mint_with_proof fails.
+    #[derive(
+        Debug, PartialEq, Eq, Clone, Copy,
+        Encode, Decode, SpreadLayout, PackedLayout
+    )]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct MintPhase {
+        pub start: Timestamp,
+        pub end: Timestamp,
+        pub price: Balance,
+        // 0 is no limit.
+        pub wallet_limit: u32,
+        // None is a public phase, otherwise only allowlisted callers.
+        pub merkle_root: Option<Hash>
+    }
+
+    // what a token key signs, together with contract, id and nonce.
+    #[derive(Debug, PartialEq, Eq, Clone, Copy, Encode, Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum TokenAction {
+        Approve(AccountId),
+        Transfer(AccountId),
+        Burn,
+        ChangePhrase(TokenSecret),
+        SetApprovalForAll(AccountId, bool),
+        SetKey(Option<TokenPublicKey>)
+    }
+
+    // lazy mint, signed off-chain by a minter key or the founder.
+    #[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct MintVoucher {
+        pub gen: TokenGen,
+        pub recipient: AccountId,
+        pub price: Balance,
+        pub expires_at: Timestamp
+    }
+
     #[ink(event)]
     pub struct Transfer {
         #[ink(topic)]
@@ -73,7 +308,9 @@ mod erc721 {
         #[ink(topic)]
         to: AccountId,
         #[ink(topic)]
-        id: TokenId
+        id: TokenId,
+        // false when the approval was revoked
+        approved: bool
     }
 
     #[ink(event)]
@@ -85,6 +322,158 @@ mod erc721 {
         approved: bool
     }
 
+    // id is None when the whole collection changed (base uri).
+    #[ink(event)]
+    pub struct MetadataUpdate {
+        #[ink(topic)]
+        id: Option<TokenId>
+    }
+
+    #[ink(event)]
+    pub struct FeesUpdated {
+        fees: Fees
+    }
+
+    #[ink(event)]
+    pub struct PayeesUpdated {
+        payees: PayeeShares
+    }
+
+    #[ink(event)]
+    pub struct PaymentReleased {
+        #[ink(topic)]
+        payee: AccountId,
+        amount: Balance
+    }
+
+    #[ink(event)]
+    pub struct MintPhaseUpdated {
+        #[ink(topic)]
+        id: MintPhaseId,
+        phase: MintPhase
+    }
+
+    #[ink(event)]
+    pub struct MintCommitted {
+        #[ink(topic)]
+        committer: AccountId,
+        #[ink(topic)]
+        commitment: MintCommitment
+    }
+
+    #[ink(event)]
+    pub struct MintCommitmentRefunded {
+        #[ink(topic)]
+        committer: AccountId,
+        #[ink(topic)]
+        commitment: MintCommitment,
+        deposit: Balance
+    }
+
+    #[ink(event)]
+    pub struct Listed {
+        #[ink(topic)]
+        id: TokenId,
+        #[ink(topic)]
+        seller: AccountId,
+        price: Balance,
+        expires_at: Timestamp
+    }
+
+    #[ink(event)]
+    pub struct Sold {
+        #[ink(topic)]
+        id: TokenId,
+        #[ink(topic)]
+        seller: AccountId,
+        #[ink(topic)]
+        buyer: AccountId,
+        price: Balance
+    }
+
+    #[ink(event)]
+    pub struct Delisted {
+        #[ink(topic)]
+        id: TokenId,
+        #[ink(topic)]
+        seller: AccountId
+    }
+
+    #[ink(event)]
+    pub struct AuctionStarted {
+        #[ink(topic)]
+        id: TokenId,
+        #[ink(topic)]
+        seller: AccountId,
+        kind: AuctionKind,
+        end: Timestamp
+    }
+
+    #[ink(event)]
+    pub struct BidPlaced {
+        #[ink(topic)]
+        id: TokenId,
+        #[ink(topic)]
+        bidder: AccountId,
+        amount: Balance
+    }
+
+    // winner is None when the token went back to the seller.
+    #[ink(event)]
+    pub struct AuctionSettled {
+        #[ink(topic)]
+        id: TokenId,
+        #[ink(topic)]
+        seller: AccountId,
+        #[ink(topic)]
+        winner: Option<AccountId>,
+        price: Balance
+    }
+
+    // user is None when the user role was cleared.
+    #[ink(event)]
+    pub struct UpdateUser {
+        #[ink(topic)]
+        id: TokenId,
+        #[ink(topic)]
+        user: Option<AccountId>,
+        expires: Timestamp
+    }
+
+    // ERC-5192, token can not be transferred or approved while locked.
+    #[ink(event)]
+    pub struct Locked {
+        #[ink(topic)]
+        id: TokenId
+    }
+
+    #[ink(event)]
+    pub struct Unlocked {
+        #[ink(topic)]
+        id: TokenId
+    }
+
+    #[ink(event)]
+    pub struct VoucherRedeemed {
+        #[ink(topic)]
+        voucher: VoucherHash,
+        #[ink(topic)]
+        recipient: AccountId,
+        #[ink(topic)]
+        id: TokenId,
+        price: Balance
+    }
+
+    // value is None when the attribute was removed.
+    #[ink(event)]
+    pub struct AttributeChanged {
+        #[ink(topic)]
+        id: TokenId,
+        #[ink(topic)]
+        key: AttributeKey,
+        value: Option<AttributeValue>
+    }
+
     #[ink(storage)]
     #[derive(Default, SpreadAllocate)]
     pub struct Erc721 {
@@ -97,127 +486,614 @@ mod erc721 {
 
         // actuary dont need to be vec.
         token_approvals: Mapping<TokenId, ApprovedAccounts>,
-        operator_approvals: Mapping<ApprovalPair, ApprovalScope>
+        operator_approvals: Mapping<ApprovalPair, ApprovalScope>,
+
+        name: CollectionName,
+        symbol: CollectionSymbol,
+        base_uri: TokenUri,
+        // overrides base_uri + id for single tokens.
+        token_uris: Mapping<TokenId, TokenUri>,
+
+        // enumeration, both lists are kept dense with swap-remove.
+        total_supply: u32,
+        all_tokens: Mapping<u32, TokenId>,
+        all_tokens_index: Mapping<TokenId, u32>,
+        owned_tokens: Mapping<(AccountId, u32), TokenId>,
+        owned_tokens_index: Mapping<TokenId, u32>,
+
+        // per-token royalty overrides the default one.
+        default_royalty: Option<Royalty>,
+        token_royalties: Mapping<TokenId, Royalty>,
+
+        fees: Fees,
+        fee_exempt_accounts: Mapping<AccountId, ()>,
+
+        // per committer, a copied commitment cannot block the original one.
+        pending_mints: Mapping<(AccountId, MintCommitment), PendingMint>,
+        // while set, gens can only be claimed with commit_mint/reveal_mint,
+        // so a revealed gen cannot be front-run by a direct mint.
+        commit_reveal_only: bool,
+
+        // signatures by the token key can be used instead of the phrase.
+        token_keys: Mapping<TokenId, TokenPublicKey>,
+        // never removed, so signatures cannot be replayed after re-mint.
+        token_nonces: Mapping<TokenId, TokenNonce>,
+
+        // reverse lookup of string_to_hash_internal
+        token_gens: Mapping<TokenId, TokenGen>,
+
+        // fixed-price sales, listed ids are kept dense for paging.
+        listings: Mapping<TokenId, Listing>,
+        listing_count: u32,
+        listed_tokens: Mapping<u32, TokenId>,
+        listed_tokens_index: Mapping<TokenId, u32>,
+
+        auctions: Mapping<TokenId, Auction>,
+        // outbid english bids, claimed with withdraw_pending_returns.
+        pending_returns: Mapping<AccountId, Balance>,
+        // part of contract balance owed to users (bids, deposits),
+        // never collected by the founder.
+        escrowed_funds: Balance,
+
+        // (user, expires), user role is gone after expires.
+        token_users: Mapping<TokenId, (AccountId, Timestamp)>,
+
+        // soulbound tokens, locked at mint by the founder.
+        locked_tokens: Mapping<TokenId, ()>,
+
+        // keys allowed to sign mint vouchers besides the founder.
+        minter_keys: Mapping<TokenPublicKey, ()>,
+        // never removed, so vouchers cannot be replayed after burn.
+        redeemed_vouchers: Mapping<VoucherHash, ()>,
+
+        mint_phases: Mapping<MintPhaseId, MintPhase>,
+        mint_phase_count: MintPhaseId,
+        minted_in_phases: Mapping<(MintPhaseId, AccountId), u32>,
+
+        // bounds minted_count, burned tokens do not free supply.
+        max_supply: Option<u32>,
+        minted_count: u32,
+        burned_count: u32,
+        // 0 is no limit.
+        wallet_mint_limit: u32,
+        minted_by: Mapping<AccountId, u32>,
+
+        // never removed, a burned gen cannot be minted again.
+        burned_tokens: Mapping<TokenId, ()>,
+
+        // accounts allowed to write attributes besides the founder.
+        attribute_updaters: Mapping<AccountId, ()>,
+        token_attributes: Mapping<(TokenId, AttributeKey), AttributeValue>,
+        // keys in insertion order, for listing the attributes of a token.
+        token_attribute_keys: Mapping<TokenId, AttributeKeyList>,
+
+        // collected funds are split by shares, founder alone by default.
+        payees: PayeeList,
+        payee_shares: Mapping<AccountId, u32>,
+        total_payee_shares: u32,
+        released: Mapping<AccountId, Balance>,
+        total_released: Balance
     }
 
     impl Erc721 {
         #[ink(constructor)]
-        pub fn new() -> Self {
+        pub fn new(name: CollectionName, symbol: CollectionSymbol) -> Self {
             ink_lang::utils::initialize_contract(|cnt: &mut Self| {
-                cnt.init_contract_internal();
+                cnt.init_contract_internal(name, symbol);
             })
         }
 
         // self construct
         #[inline]
-        fn init_contract_internal(&mut self) {
+        fn init_contract_internal(
+            &mut self, name: CollectionName, symbol: CollectionSymbol
+        ) {
             self.founder = self.env().caller();
             self.burn_account = AccountId::from([0x00; 32]);
+            self.name = name;
+            self.symbol = symbol;
+            self.fees = Fees {
+                mint: DEFAULT_MINT_FEE,
+                burn: DEFAULT_BURN_FEE,
+                transfer: DEFAULT_TRANSFER_FEE
+            };
+            self.payees = ink_prelude::vec![self.founder];
+            self.payee_shares.insert(self.founder, &1);
+            self.total_payee_shares = 1;
         }
 
         #[ink(message)]
-        pub fn collect_funded_all(&mut self) -> Erc721Result {
-            let caller = self.env().caller();
-            if caller != self.founder {
-                return Err(Error::FounderOnly)
-            }
-
-            let amount = self.env().balance();
-            if self.transfer_funded_internal(&amount).is_err() {
-                return Err(Error::NotEnoughBalance)
-            }
-            Ok(())
+        pub fn name(&self) -> CollectionName {
+            self.name.clone()
         }
 
-        #[inline]
-        fn transfer_funded_internal(&self, amount: &Balance
-        ) -> ink_env::Result<()> {
-            self.env().transfer(self.founder, *amount)
+        #[ink(message)]
+        pub fn symbol(&self) -> CollectionSymbol {
+            self.symbol.clone()
         }
 
         #[ink(message)]
-        pub fn balance_of(&self, owner: AccountId) -> u32 {
-            self.balance_of_or_zero_internal(&owner)
+        pub fn base_uri(&self) -> TokenUri {
+            self.base_uri.clone()
         }
 
-        #[inline]
-        fn balance_of_or_zero_internal(&self, owner: &AccountId) -> u32 {
-            self.owned_tokens_count.get(owner).unwrap_or(0)
+        #[ink(message)]
+        pub fn set_base_uri(&mut self, base_uri: TokenUri) -> Erc721Result {
+            if self.env().caller() != self.founder {
+                return Err(Error::FounderOnly)
+            }
+
+            self.base_uri = base_uri;
+            self.env().emit_event(MetadataUpdate { id: None });
+            Ok(())
         }
 
+        // None clears the override, then base uri is used again.
         #[ink(message)]
-        pub fn owner_of(&self, gen: TokenGen) -> Option<AccountId> {
+        pub fn set_token_uri(&mut self, gen: TokenGen, uri: Option<TokenUri>
+        ) -> Erc721Result {
+            if self.env().caller() != self.founder {
+                return Err(Error::FounderOnly)
+            }
+
             let id = self.string_to_hash_internal(&gen);
-            self.owner_of_internal(&id)
+            if !self.token_owners.contains(id) {
+                return Err(Error::TokenNotFound)
+            }
+
+            match uri {
+                Some(u) => self.token_uris.insert(id, &u),
+                None => self.token_uris.remove(id)
+            }
+            self.env().emit_event(MetadataUpdate { id: Some(id) });
+            Ok(())
         }
 
-        #[inline]
-        fn owner_of_internal(&self, id: &TokenId) -> Option<AccountId> {
-            self.token_owners.get(id)
+        #[ink(message)]
+        pub fn token_uri(&self, gen: TokenGen) -> Option<TokenUri> {
+            let id = self.string_to_hash_internal(&gen);
+            self.token_uri_internal(&id)
         }
 
         #[ink(message)]
-        pub fn get_approved(&self, gen: TokenGen) -> Option<ApprovedAccounts> {
-            let id = self.string_to_hash_internal(&gen); 
-            self.get_approved_internal(&id)
+        pub fn token_uri_by_id(&self, id: TokenId) -> Option<TokenUri> {
+            self.token_uri_internal(&id)
         }
 
-        #[inline]
-        fn get_approved_internal(&self, id: &TokenId) -> Option<ApprovedAccounts> {
-            self.token_approvals.get(&id)
+        // override if any, otherwise base uri followed by hex encoded id.
+        fn token_uri_internal(&self, id: &TokenId) -> Option<TokenUri> {
+            if !self.token_owners.contains(id) {
+                return None
+            }
+            if let Some(uri) = self.token_uris.get(id) {
+                return Some(uri)
+            }
+            if self.base_uri.is_empty() {
+                return None
+            }
+
+            const HEX: &[u8; 16] = b"0123456789abcdef";
+            let mut uri = self.base_uri.clone();
+            for b in id.as_ref() {
+                uri.push(HEX[(b >> 4) as usize] as char);
+                uri.push(HEX[(b & 0x0f) as usize] as char);
+            }
+            Some(uri)
         }
 
         #[ink(message)]
-        pub fn is_approved_for_all(
-            &self, owner: AccountId, operator: AccountId
-        ) -> bool {
-            self.is_approved_for_all_internal(&owner, &operator)
+        pub fn payees(&self) -> PayeeShares {
+            self.payees
+                .iter()
+                .map(|p| (*p, self.payee_shares.get(p).unwrap_or(0)))
+                .collect()
         }
 
-        #[inline]
-        fn is_approved_for_all_internal(
-            &self, owner: &AccountId, operator: &AccountId
-        ) -> bool {
-            self.operator_approvals.contains((owner, operator))
-        }
+        // what old payees are owed is paid out before the table changes.
+        #[ink(message)]
+        pub fn set_payees(&mut self, payees: PayeeShares) -> Erc721Result {
+            if self.env().caller() != self.founder {
+                return Err(Error::FounderOnly)
+            }
 
-        fn is_approved_internal(
-            &self, owner: &AccountId, id: &TokenId, operator: &AccountId
-        ) -> bool {
-            if self.is_approved_for_all_internal(owner, operator) {
-                return true
+            let mut total: u32 = 0;
+            for (i, (payee, shares)) in payees.iter().enumerate() {
+                if *shares == 0 || payees[..i].iter().any(|(p, _)| p == payee) {
+                    return Err(Error::InvalidPayees)
+                }
+                total = total.checked_add(*shares).ok_or(Error::InvalidPayees)?;
             }
-                      
-            match self.get_approved_internal(id) {
-                Some(v) => {
-                    return v.contains(operator)
-                },
-                None => {
-                    return false
+            if total == 0 {
+                return Err(Error::InvalidPayees)
+            }
+
+            for payee in self.payees.clone().iter() {
+                let amount = self.releasable_internal(payee);
+                if amount > 0 {
+                    self.release_internal(payee, amount)?;
                 }
             }
+            for payee in self.payees.iter() {
+                self.payee_shares.remove(payee);
+                self.released.remove(payee);
+            }
+            self.total_released = 0;
+
+            for (payee, shares) in payees.iter() {
+                self.payee_shares.insert(payee, shares);
+            }
+            self.payees = payees.iter().map(|(p, _)| *p).collect();
+            self.total_payee_shares = total;
+            self.env().emit_event(PayeesUpdated { payees });
+            Ok(())
         }
 
+        // anyone can trigger the payout, funds always go to the payee.
         #[ink(message)]
-        pub fn change_phrase(
-            &mut self, gen: TokenGen,
-            current_phrase: TokenPhrase, new_phrase: TokenPhrase
-        ) -> Erc721Result {
-            let caller = self.env().caller();
-            let id = self.string_to_hash_internal(&gen);
-            let owner = self.token_owners.get(&id).ok_or(Error::TokenNotFound)?;
-            if caller != owner {
-                return Err(Error::NotTokenOwner)
+        pub fn release(&mut self, payee: AccountId) -> Erc721Result {
+            if !self.payee_shares.contains(payee) {
+                return Err(Error::NotPayee)
             }
 
-            let current_secret = self.string_to_hash_internal(&current_phrase);
-            let stored_secret = self.token_secrets.get(&id).ok_or(Error::StorageDataInconsistency)?;
-            if current_secret != stored_secret {
-                return Err(Error::NotTokenOwner)
+            let amount = self.releasable_internal(&payee);
+            if amount == 0 {
+                return Err(Error::NoPaymentDue)
             }
+            self.release_internal(&payee, amount)
+        }
 
-            self.change_phrase_internal(&id, &new_phrase);
-            Ok(())
+        #[ink(message)]
+        pub fn released(&self, payee: AccountId) -> Balance {
+            self.released.get(payee).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        pub fn releasable(&self, payee: AccountId) -> Balance {
+            self.releasable_internal(&payee)
+        }
+
+        // payee's part of everything received since the payees were set,
+        // escrowed funds are not received yet.
+        fn releasable_internal(&self, payee: &AccountId) -> Balance {
+            let shares = self.payee_shares.get(payee).unwrap_or(0);
+            if shares == 0 {
+                return 0
+            }
+            let received = self.env().balance()
+                .saturating_sub(self.escrowed_funds)
+                .saturating_add(self.total_released);
+            let due = received.saturating_mul(shares.into())
+                / Balance::from(self.total_payee_shares);
+            due.saturating_sub(self.released.get(payee).unwrap_or(0))
+        }
+
+        fn release_internal(&mut self, payee: &AccountId, amount: Balance
+        ) -> Erc721Result {
+            self.pay_out_internal(payee, amount)?;
+            let released = self.released.get(payee).unwrap_or(0);
+            self.released.insert(payee, &released.saturating_add(amount));
+            self.total_released = self.total_released.saturating_add(amount);
+            self.env().emit_event(
+                PaymentReleased {
+                    payee: *payee,
+                    amount
+                }
+            );
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn fees(&self) -> Fees {
+            self.fees
+        }
+
+        #[ink(message)]
+        pub fn set_fees(&mut self, fees: Fees) -> Erc721Result {
+            if self.env().caller() != self.founder {
+                return Err(Error::FounderOnly)
+            }
+
+            self.fees = fees;
+            self.env().emit_event(FeesUpdated { fees });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_fee_exempt(&self, account: AccountId) -> bool {
+            self.fee_exempt_accounts.contains(account)
+        }
+
+        #[ink(message)]
+        pub fn set_fee_exempt(&mut self, account: AccountId, exempt: bool
+        ) -> Erc721Result {
+            if self.env().caller() != self.founder {
+                return Err(Error::FounderOnly)
+            }
+
+            if exempt {
+                self.fee_exempt_accounts.insert(account, &());
+            } else {
+                self.fee_exempt_accounts.remove(account);
+            }
+            Ok(())
+        }
+
+        // returns the fee caller has to pay, checking it was transferred.
+        fn required_fee_internal(&self, fee: Balance) -> Result<Balance, Error> {
+            let required = if self.fee_exempt_accounts.contains(self.env().caller()) {
+                0
+            } else {
+                fee
+            };
+            if self.env().transferred_value() < required {
+                return Err(Error::NotEnoughFee)
+            }
+            Ok(required)
+        }
+
+        // call only after the operation succeeded.
+        fn refund_overpaid_internal(&self, fee: Balance) -> Erc721Result {
+            let overpaid = self.env().transferred_value() - fee;
+            self.pay_out_internal(&self.env().caller(), overpaid)
+        }
+
+        #[ink(message)]
+        pub fn balance_of(&self, owner: AccountId) -> u32 {
+            self.balance_of_or_zero_internal(&owner)
+        }
+
+        #[inline]
+        fn balance_of_or_zero_internal(&self, owner: &AccountId) -> u32 {
+            self.owned_tokens_count.get(owner).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        pub fn total_supply(&self) -> u32 {
+            self.total_supply
+        }
+
+        #[ink(message)]
+        pub fn token_by_index(&self, index: u32) -> Option<TokenId> {
+            self.all_tokens.get(index)
+        }
+
+        #[ink(message)]
+        pub fn token_of_owner_by_index(&self, owner: AccountId, index: u32
+        ) -> Option<TokenId> {
+            self.owned_tokens.get((owner, index))
+        }
+
+        #[ink(message)]
+        pub fn tokens_of_owner(&self, owner: AccountId, offset: u32, limit: u32
+        ) -> TokenIdList {
+            let count = self.balance_of_or_zero_internal(&owner);
+            let end = offset.saturating_add(limit).min(count);
+            let mut list = ink_prelude::vec![];
+            for i in offset..end {
+                if let Some(id) = self.owned_tokens.get((owner, i)) {
+                    list.push(id);
+                }
+            }
+            list
+        }
+
+        #[ink(message)]
+        pub fn owner_of(&self, gen: TokenGen) -> Option<AccountId> {
+            let id = self.string_to_hash_internal(&gen);
+            self.owner_of_internal(&id)
+        }
+
+        #[ink(message)]
+        pub fn owner_of_id(&self, id: TokenId) -> Option<AccountId> {
+            self.owner_of_internal(&id)
+        }
+
+        #[inline]
+        fn owner_of_internal(&self, id: &TokenId) -> Option<AccountId> {
+            self.token_owners.get(id)
+        }
+
+        #[ink(message)]
+        pub fn token_exists(&self, id: TokenId) -> bool {
+            self.token_owners.contains(id)
+        }
+
+        #[ink(message)]
+        pub fn token_id(&self, gen: TokenGen) -> TokenId {
+            self.string_to_hash_internal(&gen)
+        }
+
+        #[ink(message)]
+        pub fn gen_of(&self, id: TokenId) -> Option<TokenGen> {
+            self.token_gens.get(id)
+        }
+
+        #[ink(message)]
+        pub fn get_approved(&self, gen: TokenGen) -> Option<ApprovedAccounts> {
+            let id = self.string_to_hash_internal(&gen); 
+            self.get_approved_internal(&id)
+        }
+
+        #[inline]
+        fn get_approved_internal(&self, id: &TokenId) -> Option<ApprovedAccounts> {
+            self.token_approvals.get(&id)
+        }
+
+        #[ink(message)]
+        pub fn get_approved_by_id(&self, id: TokenId) -> Option<ApprovedAccounts> {
+            self.get_approved_internal(&id)
+        }
+
+        // erc721 knows only one approved account, this is the latest one.
+        #[ink(message)]
+        pub fn get_approved_single(&self, gen: TokenGen) -> Option<AccountId> {
+            let id = self.string_to_hash_internal(&gen);
+            self.get_approved_internal(&id)
+                .and_then(|v| v.last().copied())
+        }
+
+        #[ink(message)]
+        pub fn is_approved_for_all(
+            &self, owner: AccountId, operator: AccountId
+        ) -> bool {
+            self.is_approved_for_all_internal(&owner, &operator)
+        }
+
+        #[inline]
+        fn is_approved_for_all_internal(
+            &self, owner: &AccountId, operator: &AccountId
+        ) -> bool {
+            self.operator_approvals.contains((owner, operator))
+        }
+
+        fn is_approved_internal(
+            &self, owner: &AccountId, id: &TokenId, operator: &AccountId
+        ) -> bool {
+            if self.is_approved_for_all_internal(owner, operator) {
+                return true
+            }
+                      
+            match self.get_approved_internal(id) {
+                Some(v) => {
+                    return v.contains(operator)
+                },
+                None => {
+                    return false
+                }
+            }
+        }
+
+        #[ink(message)]
+        pub fn change_phrase(
+            &mut self, gen: TokenGen,
+            current_phrase: TokenPhrase, new_phrase: TokenPhrase
+        ) -> Erc721Result {
+            let caller = self.env().caller();
+            let id = self.string_to_hash_internal(&gen);
+            let owner = self.token_owners.get(&id).ok_or(Error::TokenNotFound)?;
+            if caller != owner {
+                return Err(Error::NotTokenOwner)
+            }
+
+            let current_secret = self.string_to_hash_internal(&current_phrase);
+            let stored_secret = self.token_secrets.get(&id).ok_or(Error::StorageDataInconsistency)?;
+            if current_secret != stored_secret {
+                return Err(Error::NotTokenOwner)
+            }
+
+            self.change_phrase_internal(&id, &new_phrase);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn change_phrase_signed(
+            &mut self, gen: TokenGen,
+            new_phrase: TokenPhrase, signature: TokenSignature
+        ) -> Erc721Result {
+            let id = self.string_to_hash_internal(&gen);
+            let new_secret = self.string_to_hash_internal(&new_phrase);
+            self.check_signed_owner_internal(
+                &id, &TokenAction::ChangePhrase(new_secret), &signature
+            )?;
+
+            self.change_phrase_internal(&id, &new_phrase);
+            self.use_nonce_internal(&id);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn token_key(&self, id: TokenId) -> Option<TokenPublicKey> {
+            self.token_keys.get(id)
+        }
+
+        #[ink(message)]
+        pub fn token_nonce(&self, id: TokenId) -> TokenNonce {
+            self.token_nonces.get(id).unwrap_or(0)
+        }
+
+        // None turns signature mode off for the token.
+        #[ink(message)]
+        pub fn set_token_key(
+            &mut self, gen: TokenGen, phrase: TokenPhrase, key: Option<TokenPublicKey>
+        ) -> Erc721Result {
+            let id = self.string_to_hash_internal(&gen);
+            self.check_owner_phrase_internal(&id, &phrase)?;
+
+            self.set_token_key_internal(&id, &key);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn set_token_key_signed(
+            &mut self, gen: TokenGen, key: Option<TokenPublicKey>, signature: TokenSignature
+        ) -> Erc721Result {
+            let id = self.string_to_hash_internal(&gen);
+            self.check_signed_owner_internal(&id, &TokenAction::SetKey(key), &signature)?;
+
+            self.set_token_key_internal(&id, &key);
+            self.use_nonce_internal(&id);
+            Ok(())
+        }
+
+        #[inline]
+        fn set_token_key_internal(&mut self, id: &TokenId, key: &Option<TokenPublicKey>) {
+            match key {
+                Some(k) => self.token_keys.insert(id, k),
+                None => self.token_keys.remove(id)
+            }
+        }
+
+        // caller has to own the token and know its phrase.
+        fn check_owner_phrase_internal(&self, id: &TokenId, phrase: &TokenPhrase
+        ) -> Result<AccountId, Error> {
+            let owner = self.owner_of_internal(id).ok_or(Error::TokenNotFound)?;
+            if self.env().caller() != owner {
+                return Err(Error::NotTokenOwner)
+            }
+            let secret = self.string_to_hash_internal(phrase);
+            let stored_secret
+                = self.token_secrets.get(id).ok_or(Error::StorageDataInconsistency)?;
+            if secret != stored_secret {
+                return Err(Error::NotTokenOwner)
+            }
+            Ok(owner)
+        }
+
+        // caller has to own the token and the signature has to match its key.
+        fn check_signed_owner_internal(
+            &self, id: &TokenId, action: &TokenAction, signature: &TokenSignature
+        ) -> Erc721Result {
+            let owner = self.owner_of_internal(id).ok_or(Error::TokenNotFound)?;
+            if self.env().caller() != owner {
+                return Err(Error::NotTokenOwner)
+            }
+            self.check_signature_internal(id, action, signature)
+        }
+
+        fn check_signature_internal(
+            &self, id: &TokenId, action: &TokenAction, signature: &TokenSignature
+        ) -> Erc721Result {
+            let key = self.token_keys.get(id).ok_or(Error::TokenKeyNotSet)?;
+            let nonce = self.token_nonces.get(id).unwrap_or(0);
+
+            let mut message_hash
+                = <GenHashing as ink_env::hash::HashOutput>::Type::default();
+            ink_env::hash_encoded::<GenHashing, _>(
+                &(self.env().account_id(), id, action, nonce),
+                &mut message_hash
+            );
+            let mut recovered = [0u8; 33];
+            if ink_env::ecdsa_recover(signature, &message_hash, &mut recovered).is_err()
+                || recovered != key {
+                return Err(Error::InvalidSignature)
+            }
+            Ok(())
+        }
+
+        #[inline]
+        fn use_nonce_internal(&mut self, id: &TokenId) {
+            let nonce = self.token_nonces.get(id).unwrap_or(0);
+            self.token_nonces.insert(id, &nonce.wrapping_add(1));
         }
 
         #[inline]
@@ -228,106 +1104,1416 @@ mod erc721 {
             self.token_secrets.insert(&id, &new_secret);
         }
 
-        #[ink(message, payable)]
-        pub fn mint(&mut self, gen: TokenGen, phrase: TokenPhrase
+        #[ink(message, payable)]
+        pub fn mint(&mut self, gen: TokenGen, phrase: TokenPhrase
+        ) -> Erc721Result {
+            let caller = self.env().caller();
+            if caller == self.burn_account {
+                return Err(Error::AccountToBurn)
+            }
+            self.check_direct_mint_internal()?;
+            self.check_no_open_phase_internal()?;
+
+            let fee = self.required_fee_internal(self.fees.mint)?;
+            
+            self.mint_token_internal(&caller, &gen, &phrase)?;
+            self.refund_overpaid_internal(fee)
+        }
+
+        // all or nothing, the mint fee is paid once per token.
+        #[ink(message, payable)]
+        pub fn mint_batch(&mut self, pairs: TokenGenPhrasePairs) -> Erc721Result {
+            let caller = self.env().caller();
+            if caller == self.burn_account {
+                return Err(Error::AccountToBurn)
+            }
+            self.check_direct_mint_internal()?;
+            self.check_no_open_phase_internal()?;
+
+            let size = u32::try_from(pairs.len()).map_err(|_| Error::ReachingMax)?;
+            let total_fee = self.fees.mint
+                .checked_mul(size.into())
+                .ok_or(Error::ReachingMax)?;
+            let fee = self.required_fee_internal(total_fee)?;
+
+            let count = self.balance_of_or_zero_internal(&caller);
+            let new_count = count.checked_add(size).ok_or(Error::ReachingMax)?;
+            if self.total_supply.checked_add(size).is_none() {
+                return Err(Error::ReachingMax)
+            }
+            let mut minted: ink_prelude::vec::Vec<(TokenId, TokenSecret)>
+                = ink_prelude::vec::Vec::with_capacity(pairs.len());
+            for (gen, ph) in pairs.iter() {
+                let (id, secret) = self.check_mint_internal(gen, ph)?;
+                if minted.iter().any(|(i, _)| *i == id) {
+                    return Err(Error::DuplicateToken)
+                }
+                minted.push((id, secret));
+            }
+            self.check_mint_limits_internal(&caller, size)?;
+
+            for (index, ((id, secret), (gen, _))) in
+                (count..new_count).zip(minted.iter().zip(pairs.iter())) {
+                self.attach_token_internal(&caller, id, index);
+                self.store_minted_token_internal(&caller, id, secret, gen)?;
+            }
+            self.owned_tokens_count.insert(caller, &new_count);
+            self.count_mints_internal(&caller, size);
+            self.refund_overpaid_internal(fee)
+        }
+
+        #[ink(message)]
+        pub fn add_mint_phase(&mut self, phase: MintPhase) -> Result<MintPhaseId, Error> {
+            if self.env().caller() != self.founder {
+                return Err(Error::FounderOnly)
+            }
+            if phase.start >= phase.end {
+                return Err(Error::InvalidMintPhase)
+            }
+
+            let id = self.mint_phase_count;
+            self.mint_phase_count = id.checked_add(1).ok_or(Error::ReachingMax)?;
+            self.mint_phases.insert(id, &phase);
+            self.env().emit_event(MintPhaseUpdated { id, phase });
+            Ok(id)
+        }
+
+        // counts minted in the phase are kept.
+        #[ink(message)]
+        pub fn update_mint_phase(&mut self, id: MintPhaseId, phase: MintPhase
+        ) -> Erc721Result {
+            if self.env().caller() != self.founder {
+                return Err(Error::FounderOnly)
+            }
+            if !self.mint_phases.contains(id) {
+                return Err(Error::MintPhaseNotFound)
+            }
+            if phase.start >= phase.end {
+                return Err(Error::InvalidMintPhase)
+            }
+
+            self.mint_phases.insert(id, &phase);
+            self.env().emit_event(MintPhaseUpdated { id, phase });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn mint_phase(&self, id: MintPhaseId) -> Option<MintPhase> {
+            self.mint_phases.get(id)
+        }
+
+        #[ink(message)]
+        pub fn current_phase(&self) -> Option<(MintPhaseId, MintPhase)> {
+            let now = self.env().block_timestamp();
+            (0..self.mint_phase_count)
+                .filter_map(|id| self.mint_phases.get(id).map(|p| (id, p)))
+                .find(|(_, p)| p.start <= now && now < p.end)
+        }
+
+        // in the current phase, 0 when no phase is open.
+        #[ink(message)]
+        pub fn minted_in_phase(&self, account: AccountId) -> u32 {
+            match self.current_phase() {
+                Some((id, _)) => self.minted_in_phases.get((id, account)).unwrap_or(0),
+                None => 0
+            }
+        }
+
+        // public mints other than mint_with_proof would skip the phase
+        // allowlist, price and wallet limit.
+        fn check_no_open_phase_internal(&self) -> Erc721Result {
+            if self.current_phase().is_some() {
+                return Err(Error::MintPhaseActive)
+            }
+            Ok(())
+        }
+
+        // mints in the current phase, paying its price instead of the mint fee.
+        #[ink(message, payable)]
+        pub fn mint_with_proof(
+            &mut self, gen: TokenGen, phrase: TokenPhrase, proof: MerkleProof
+        ) -> Erc721Result {
+            let caller = self.env().caller();
+            if caller == self.burn_account {
+                return Err(Error::AccountToBurn)
+            }
+            self.check_direct_mint_internal()?;
+
+            let (id, phase) = self.current_phase().ok_or(Error::NoActiveMintPhase)?;
+            if let Some(root) = phase.merkle_root {
+                if !Self::verify_merkle_proof_internal(&caller, &proof, &root) {
+                    return Err(Error::InvalidMerkleProof)
+                }
+            }
+            let minted = self.minted_in_phases.get((id, caller)).unwrap_or(0);
+            if phase.wallet_limit != 0 && minted >= phase.wallet_limit {
+                return Err(Error::PhaseLimitReached)
+            }
+            if self.env().transferred_value() < phase.price {
+                return Err(Error::NotEnoughFee)
+            }
+
+            self.mint_token_internal(&caller, &gen, &phrase)?;
+            self.minted_in_phases.insert((id, caller), &minted.saturating_add(1));
+            self.refund_overpaid_internal(phase.price)
+        }
+
+        // leaf is blake2x256 of the account bytes, every node is blake2x256
+        // of its two children concatenated in ascending order.
+        fn verify_merkle_proof_internal(
+            account: &AccountId, proof: &MerkleProof, root: &Hash
+        ) -> bool {
+            let mut node = <GenHashing as ink_env::hash::HashOutput>::Type::default();
+            ink_env::hash_bytes::<GenHashing>(account.as_ref(), &mut node);
+            for sibling in proof.iter() {
+                let sibling: &[u8] = sibling.as_ref();
+                let mut pair = [0u8; 64];
+                if node[..] <= *sibling {
+                    pair[..32].copy_from_slice(&node);
+                    pair[32..].copy_from_slice(sibling);
+                } else {
+                    pair[..32].copy_from_slice(sibling);
+                    pair[32..].copy_from_slice(&node);
+                }
+                ink_env::hash_bytes::<GenHashing>(&pair, &mut node);
+            }
+            Hash::from(node) == *root
+        }
+
+        // minter sets the royalty of the new token, overriding the default one.
+        #[ink(message, payable)]
+        pub fn mint_with_royalty(
+            &mut self, gen: TokenGen, phrase: TokenPhrase, royalty: Royalty
+        ) -> Erc721Result {
+            let caller = self.env().caller();
+            if caller == self.burn_account {
+                return Err(Error::AccountToBurn)
+            }
+            self.check_direct_mint_internal()?;
+            self.check_no_open_phase_internal()?;
+
+            let fee = self.required_fee_internal(self.fees.mint)?;
+
+            Self::check_royalty_internal(&royalty)?;
+            let id = self.mint_token_internal(&caller, &gen, &phrase)?;
+            self.token_royalties.insert(id, &royalty);
+            self.refund_overpaid_internal(fee)
+        }
+
+        // soulbound mint, founder gives a locked token to the account.
+        #[ink(message)]
+        pub fn mint_locked(
+            &mut self, to: AccountId, gen: TokenGen, phrase: TokenPhrase
+        ) -> Erc721Result {
+            if self.env().caller() != self.founder {
+                return Err(Error::FounderOnly)
+            }
+            if to == self.burn_account {
+                return Err(Error::AccountToBurn)
+            }
+
+            let id = self.mint_token_internal(&to, &gen, &phrase)?;
+            self.locked_tokens.insert(id, &());
+            self.env().emit_event(Locked { id });
+            Ok(())
+        }
+
+        // makes a locked token transferable again, cannot be undone.
+        #[ink(message)]
+        pub fn unlock(&mut self, gen: TokenGen) -> Erc721Result {
+            if self.env().caller() != self.founder {
+                return Err(Error::FounderOnly)
+            }
+
+            let id = self.string_to_hash_internal(&gen);
+            if !self.token_owners.contains(id) {
+                return Err(Error::TokenNotFound)
+            }
+            if !self.locked_tokens.contains(id) {
+                return Ok(())
+            }
+            self.locked_tokens.remove(id);
+            self.env().emit_event(Unlocked { id });
+            Ok(())
+        }
+
+        // None when the token does not exist.
+        #[ink(message)]
+        pub fn locked(&self, id: TokenId) -> Option<bool> {
+            if !self.token_owners.contains(id) {
+                return None
+            }
+            Some(self.locked_tokens.contains(id))
+        }
+
+        fn check_not_locked_internal(&self, id: &TokenId) -> Erc721Result {
+            if self.locked_tokens.contains(id) {
+                return Err(Error::TokenLocked)
+            }
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn set_minter_key(&mut self, key: TokenPublicKey, allowed: bool
+        ) -> Erc721Result {
+            if self.env().caller() != self.founder {
+                return Err(Error::FounderOnly)
+            }
+
+            if allowed {
+                self.minter_keys.insert(key, &());
+            } else {
+                self.minter_keys.remove(key);
+            }
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_minter_key(&self, key: TokenPublicKey) -> bool {
+            self.minter_keys.contains(key)
+        }
+
+        // recipient picks the phrase and pays the voucher price instead of the mint fee.
+        #[ink(message, payable)]
+        pub fn redeem_voucher(
+            &mut self,
+            voucher: MintVoucher,
+            signature: TokenSignature,
+            phrase: TokenPhrase
+        ) -> Erc721Result {
+            let caller = self.env().caller();
+            if caller != voucher.recipient {
+                return Err(Error::NotVoucherRecipient)
+            }
+            if caller == self.burn_account {
+                return Err(Error::AccountToBurn)
+            }
+            if self.env().block_timestamp() > voucher.expires_at {
+                return Err(Error::VoucherExpired)
+            }
+
+            let hash = self.voucher_hash_internal(&voucher);
+            if self.redeemed_vouchers.contains(hash) {
+                return Err(Error::VoucherAlreadyRedeemed)
+            }
+            self.check_voucher_signer_internal(&hash, &signature)?;
+            if self.env().transferred_value() < voucher.price {
+                return Err(Error::NotEnoughFee)
+            }
+
+            let id = self.mint_token_internal(&caller, &voucher.gen, &phrase)?;
+            self.redeemed_vouchers.insert(hash, &());
+            self.env().emit_event(
+                VoucherRedeemed {
+                    voucher: hash,
+                    recipient: caller,
+                    id,
+                    price: voucher.price
+                }
+            );
+            self.refund_overpaid_internal(voucher.price)
+        }
+
+        #[ink(message)]
+        pub fn voucher_hash(&self, voucher: MintVoucher) -> VoucherHash {
+            self.voucher_hash_internal(&voucher)
+        }
+
+        #[ink(message)]
+        pub fn voucher_redeemed(&self, hash: VoucherHash) -> bool {
+            self.redeemed_vouchers.contains(hash)
+        }
+
+        fn voucher_hash_internal(&self, voucher: &MintVoucher) -> VoucherHash {
+            let mut output
+                = <GenHashing as ink_env::hash::HashOutput>::Type::default();
+            ink_env::hash_encoded::<GenHashing, _>(
+                &(self.env().account_id(), voucher),
+                &mut output
+            );
+            VoucherHash::from(output)
+        }
+
+        // founder signs with the ecdsa key its account id is derived from.
+        fn check_voucher_signer_internal(
+            &self, hash: &VoucherHash, signature: &TokenSignature
+        ) -> Erc721Result {
+            let mut message = [0u8; 32];
+            message.copy_from_slice(hash.as_ref());
+            let mut recovered = [0u8; 33];
+            if ink_env::ecdsa_recover(signature, &message, &mut recovered).is_err() {
+                return Err(Error::InvalidSignature)
+            }
+            if self.minter_keys.contains(recovered) {
+                return Ok(())
+            }
+
+            let mut account
+                = <GenHashing as ink_env::hash::HashOutput>::Type::default();
+            ink_env::hash_bytes::<GenHashing>(&recovered, &mut account);
+            if AccountId::from(account) != self.founder {
+                return Err(Error::InvalidSignature)
+            }
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn commit_reveal_only(&self) -> bool {
+            self.commit_reveal_only
+        }
+
+        // turning it off closes commit_mint, pending commitments can still be
+        // revealed but are open to front-running again.
+        #[ink(message)]
+        pub fn set_commit_reveal_only(&mut self, enabled: bool) -> Erc721Result {
+            if self.env().caller() != self.founder {
+                return Err(Error::FounderOnly)
+            }
+
+            self.commit_reveal_only = enabled;
+            Ok(())
+        }
+
+        // for public mints that carry the gen in plain text,
+        // founder mints and vouchers are not affected.
+        fn check_direct_mint_internal(&self) -> Erc721Result {
+            if self.commit_reveal_only {
+                return Err(Error::CommitRevealRequired)
+            }
+            Ok(())
+        }
+
+        // first phase, hides gen and phrase until reveal_mint.
+        // only in commit_reveal_only mode, otherwise the revealed gen
+        // could be taken with a direct mint.
+        #[ink(message, payable)]
+        pub fn commit_mint(&mut self, commitment: MintCommitment) -> Erc721Result {
+            let caller = self.env().caller();
+            if caller == self.burn_account {
+                return Err(Error::AccountToBurn)
+            }
+            if !self.commit_reveal_only {
+                return Err(Error::CommitRevealDisabled)
+            }
+            self.check_no_open_phase_internal()?;
+
+            if self.pending_mints.contains((caller, commitment)) {
+                return Err(Error::CommitmentAlreadyExists)
+            }
+
+            let fee = self.required_fee_internal(self.fees.mint)?;
+            self.escrowed_funds = self.escrowed_funds
+                .checked_add(fee)
+                .ok_or(Error::ReachingMax)?;
+            self.pending_mints.insert((caller, commitment), &PendingMint {
+                committer: caller,
+                deposit: fee,
+                committed_at: self.env().block_number()
+            });
+            self.env().emit_event(
+                MintCommitted {
+                    committer: caller,
+                    commitment
+                }
+            );
+            self.refund_overpaid_internal(fee)
+        }
+
+        // second phase, deposit paid in commit_mint is kept as the mint fee.
+        // closed while a mint phase is open, wait for its end or get a refund.
+        #[ink(message)]
+        pub fn reveal_mint(
+            &mut self, gen: TokenGen, phrase: TokenPhrase, salt: MintSalt
+        ) -> Erc721Result {
+            self.check_no_open_phase_internal()?;
+            let caller = self.env().caller();
+            let commitment = self.mint_commitment_internal(&gen, &phrase, &salt);
+            let pending = self.pending_mints
+                .get((caller, commitment))
+                .ok_or(Error::CommitmentNotFound)?;
+
+            let now = self.env().block_number();
+            if now < pending.committed_at.saturating_add(MIN_REVEAL_DELAY) {
+                return Err(Error::RevealTooEarly)
+            }
+            if now > pending.committed_at.saturating_add(COMMITMENT_LIFETIME) {
+                return Err(Error::CommitmentExpired)
+            }
+
+            self.mint_token_internal(&caller, &gen, &phrase)?;
+            self.pending_mints.remove((caller, commitment));
+            // deposit is earned now
+            self.escrowed_funds -= pending.deposit;
+            Ok(())
+        }
+
+        // gives the deposit of an expired commitment back to its committer.
+        #[ink(message)]
+        pub fn refund_mint_commitment(
+            &mut self, committer: AccountId, commitment: MintCommitment
+        ) -> Erc721Result {
+            let pending = self.pending_mints
+                .get((committer, commitment))
+                .ok_or(Error::CommitmentNotFound)?;
+            let now = self.env().block_number();
+            if now <= pending.committed_at.saturating_add(COMMITMENT_LIFETIME) {
+                return Err(Error::CommitmentNotExpired)
+            }
+
+            self.pending_mints.remove((committer, commitment));
+            self.escrowed_funds -= pending.deposit;
+            self.pay_out_internal(&pending.committer, pending.deposit)?;
+            self.env().emit_event(
+                MintCommitmentRefunded {
+                    committer: pending.committer,
+                    commitment,
+                    deposit: pending.deposit
+                }
+            );
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn pending_mint(&self, committer: AccountId, commitment: MintCommitment
+        ) -> Option<PendingMint> {
+            self.pending_mints.get((committer, commitment))
+        }
+
+        // the hash clients commit to, query it from a trusted node only
+        // or the gen leaks before reveal.
+        #[ink(message)]
+        pub fn mint_commitment(
+            &self, gen: TokenGen, phrase: TokenPhrase, salt: MintSalt
+        ) -> MintCommitment {
+            self.mint_commitment_internal(&gen, &phrase, &salt)
+        }
+
+        fn mint_commitment_internal(
+            &self, gen: &TokenGen, phrase: &TokenPhrase, salt: &MintSalt
+        ) -> MintCommitment {
+            let mut output
+                = <GenHashing as ink_env::hash::HashOutput>::Type::default();
+            ink_env::hash_encoded::<GenHashing, _>(&(gen, phrase, salt), &mut output);
+            MintCommitment::from(output)
+        }
+
+        fn mint_token_internal(
+            &mut self, to: &AccountId, gen: &TokenGen, phrase: &TokenPhrase
+        ) -> Result<TokenId, Error> {
+            let (id, secret) = self.check_mint_internal(gen, phrase)?;
+            self.check_mint_limits_internal(to, 1)?;
+            
+            self.add_new_token_to_internal(to, &id)?;
+            self.store_minted_token_internal(to, &id, &secret, gen)?;
+            self.count_mints_internal(to, 1);
+            Ok(id)
+        }
+
+        fn check_mint_limits_internal(&self, to: &AccountId, amount: u32
+        ) -> Erc721Result {
+            let minted = self.minted_count.checked_add(amount).ok_or(Error::ReachingMax)?;
+            if let Some(max) = self.max_supply {
+                if minted > max {
+                    return Err(Error::MaxSupplyReached)
+                }
+            }
+            if self.wallet_mint_limit != 0 {
+                let by_wallet = self.minted_by.get(to).unwrap_or(0).saturating_add(amount);
+                if by_wallet > self.wallet_mint_limit {
+                    return Err(Error::WalletMintLimitReached)
+                }
+            }
+            Ok(())
+        }
+
+        // call only after check_mint_limits_internal.
+        #[inline]
+        fn count_mints_internal(&mut self, to: &AccountId, amount: u32) {
+            self.minted_count += amount;
+            let by_wallet = self.minted_by.get(to).unwrap_or(0);
+            self.minted_by.insert(to, &by_wallet.saturating_add(amount));
+        }
+
+        #[ink(message)]
+        pub fn max_supply(&self) -> Option<u32> {
+            self.max_supply
+        }
+
+        // None removes the bound, it cannot go below minted_count.
+        #[ink(message)]
+        pub fn set_max_supply(&mut self, max_supply: Option<u32>) -> Erc721Result {
+            if self.env().caller() != self.founder {
+                return Err(Error::FounderOnly)
+            }
+            if let Some(max) = max_supply {
+                if max < self.minted_count {
+                    return Err(Error::InvalidMaxSupply)
+                }
+            }
+
+            self.max_supply = max_supply;
+            Ok(())
+        }
+
+        // every token minted so far, burned ones included.
+        #[ink(message)]
+        pub fn minted_count(&self) -> u32 {
+            self.minted_count
+        }
+
+        #[ink(message)]
+        pub fn burned_count(&self) -> u32 {
+            self.burned_count
+        }
+
+        // None when there is no max supply.
+        #[ink(message)]
+        pub fn remaining_supply(&self) -> Option<u32> {
+            self.max_supply.map(|max| max.saturating_sub(self.minted_count))
+        }
+
+        #[ink(message)]
+        pub fn wallet_mint_limit(&self) -> u32 {
+            self.wallet_mint_limit
+        }
+
+        // 0 removes the limit.
+        #[ink(message)]
+        pub fn set_wallet_mint_limit(&mut self, limit: u32) -> Erc721Result {
+            if self.env().caller() != self.founder {
+                return Err(Error::FounderOnly)
+            }
+
+            self.wallet_mint_limit = limit;
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn minted_by(&self, account: AccountId) -> u32 {
+            self.minted_by.get(account).unwrap_or(0)
+        }
+
+        fn check_mint_internal(&self, gen: &TokenGen, phrase: &TokenPhrase
+        ) -> Result<(TokenId, TokenSecret), Error> {
+            let id = self.string_to_hash_internal(gen);
+            let secret = self.string_to_hash_internal(phrase);
+            if self.token_owners.contains(id) {
+                return Err(Error::TokenAlreadyExists)
+            }
+            if self.burned_tokens.contains(id) {
+                return Err(Error::TokenBurned)
+            }
+            if self.token_secrets.contains(secret) {
+                return Err(Error::StorageDataInconsistency)
+            }
+            Ok((id, secret))
+        }
+
+        // everything of a new token except its owner.
+        fn store_minted_token_internal(
+            &mut self,
+            to: &AccountId,
+            id: &TokenId,
+            secret: &TokenSecret,
+            gen: &TokenGen
+        ) -> Erc721Result {
+            self.add_token_to_all_internal(id)?;
+            self.token_secrets.insert(id, secret);
+            self.token_gens.insert(id, gen);
+            self.env().emit_event(
+                Transfer {
+                    from: Some(self.burn_account),
+                    to: Some(*to),
+                    id: *id,
+                }
+            );
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn default_royalty(&self) -> Option<Royalty> {
+            self.default_royalty
+        }
+
+        // None removes the default royalty.
+        #[ink(message)]
+        pub fn set_default_royalty(&mut self, royalty: Option<Royalty>
+        ) -> Erc721Result {
+            if self.env().caller() != self.founder {
+                return Err(Error::FounderOnly)
+            }
+
+            if let Some(r) = &royalty {
+                Self::check_royalty_internal(r)?;
+            }
+            self.default_royalty = royalty;
+            Ok(())
+        }
+
+        /// Returns the royalty receiver and the amount owed for `sale_price`.
+        #[ink(message)]
+        pub fn royalty_info(&self, id: TokenId, sale_price: Balance
+        ) -> (AccountId, Balance) {
+            self.royalty_info_internal(&id, sale_price)
+        }
+
+        fn royalty_info_internal(&self, id: &TokenId, sale_price: Balance
+        ) -> (AccountId, Balance) {
+            match self.token_royalties.get(id).or(self.default_royalty) {
+                Some(r) => {
+                    let amount = Self::mul_div_internal(
+                        sale_price, r.rate as Balance, ROYALTY_DENOMINATOR
+                    );
+                    (r.receiver, amount)
+                },
+                None => {
+                    (self.burn_account, 0)
+                }
+            }
+        }
+
+        // value * numerator / denominator rounded down, without overflow
+        // as long as numerator <= denominator.
+        #[inline]
+        fn mul_div_internal(value: Balance, numerator: Balance, denominator: Balance
+        ) -> Balance {
+            value / denominator * numerator + value % denominator * numerator / denominator
+        }
+
+        #[inline]
+        fn check_royalty_internal(royalty: &Royalty) -> Erc721Result {
+            if royalty.rate as Balance > ROYALTY_DENOMINATOR {
+                return Err(Error::InvalidRoyaltyRate)
+            }
+            Ok(())
+        }
+ 
+        fn string_to_hash_internal(&self, gen: &TokenGen) -> TokenId {
+            let input = gen.as_bytes();
+            let mut output 
+                = <GenHashing as ink_env::hash::HashOutput>::Type::default();
+            ink_env::hash_bytes::<GenHashing>(input, &mut output);
+            TokenId::from(output)
+        }
+
+        fn add_new_token_to_internal(&mut self, to: &AccountId, id: &TokenId
+        ) -> Erc721Result {
+            let count = self.owned_tokens_count.get(to).unwrap_or(0);
+            let new_count = count.checked_add(1).ok_or(Error::ReachingMax)?;
+            
+            self.owned_tokens_count.insert(to, &new_count);
+            self.attach_token_internal(to, id, count);
+            Ok(())
+        }
+
+        // index is the owner's token count before the token is added,
+        // the caller stores the new count.
+        #[inline]
+        fn attach_token_internal(&mut self, to: &AccountId, id: &TokenId, index: u32) {
+            self.token_owners.insert(id, to);
+            // new token goes to the tail of owner's list
+            self.owned_tokens.insert((to, index), id);
+            self.owned_tokens_index.insert(id, &index);
+        }
+
+        fn add_token_to_all_internal(&mut self, id: &TokenId) -> Erc721Result {
+            let index = self.total_supply;
+            self.total_supply = index.checked_add(1).ok_or(Error::ReachingMax)?;
+            self.all_tokens.insert(index, id);
+            self.all_tokens_index.insert(id, &index);
+            Ok(())
+        }
+
+        fn remove_token_from_all_internal(&mut self, id: &TokenId) -> Erc721Result {
+            let index = self.all_tokens_index
+                .get(id)
+                .ok_or(Error::StorageDataInconsistency)?;
+            let last = self.total_supply.checked_sub(1).ok_or(Error::ReachingMin)?;
+            // move the last token into the gap
+            if index != last {
+                let last_id = self.all_tokens
+                    .get(last)
+                    .ok_or(Error::StorageDataInconsistency)?;
+                self.all_tokens.insert(index, &last_id);
+                self.all_tokens_index.insert(last_id, &index);
+            }
+            self.all_tokens.remove(last);
+            self.all_tokens_index.remove(id);
+            self.total_supply = last;
+            Ok(())
+        }
+
+        #[ink(message, payable)]
+        pub fn burn(&mut self, gen: TokenGen, phrase: TokenPhrase
+        ) -> Erc721Result {
+            let caller = self.env().caller();
+            let id = self.string_to_hash_internal(&gen);
+            let secret = self.string_to_hash_internal(&phrase);
+            let owner = self.token_owners.get(&id).ok_or(Error::TokenNotFound)?;
+            if owner != caller {
+                return Err(Error::NotTokenOwner)
+            }
+            let stored_secret = 
+                self.token_secrets.get(&id).ok_or(Error::StorageDataInconsistency)?;
+            if secret != stored_secret {
+                return Err(Error::NotTokenOwner)
+            }
+
+            let fee = self.required_fee_internal(self.fees.burn)?;
+
+            self.burn_token_internal(&owner, &id)?;
+            self.refund_overpaid_internal(fee)
+        }
+
+        #[ink(message)]
+        pub fn is_burned(&self, gen: TokenGen) -> bool {
+            let id = self.string_to_hash_internal(&gen);
+            self.burned_tokens.contains(id)
+        }
+
+        #[ink(message, payable)]
+        pub fn burn_signed(&mut self, gen: TokenGen, signature: TokenSignature
+        ) -> Erc721Result {
+            let id = self.string_to_hash_internal(&gen);
+            self.check_signed_owner_internal(&id, &TokenAction::Burn, &signature)?;
+            let fee = self.required_fee_internal(self.fees.burn)?;
+
+            let owner = self.env().caller();
+            self.burn_token_internal(&owner, &id)?;
+            self.use_nonce_internal(&id);
+            self.refund_overpaid_internal(fee)
+        }
+
+        fn burn_token_internal(&mut self, owner: &AccountId, id: &TokenId
+        ) -> Erc721Result {
+            self.remove_token_internal(owner, id)?;
+            self.remove_token_from_all_internal(id)?;
+            // !!!!!!!!!!!!!!!!!!!!!!!
+            // secret is removed here.
+            self.token_secrets.remove(id);
+            self.token_uris.remove(id);
+            self.token_royalties.remove(id);
+            self.token_keys.remove(id);
+            self.token_gens.remove(id);
+            self.locked_tokens.remove(id);
+            self.remove_attributes_internal(id);
+            self.burned_tokens.insert(id, &());
+            self.burned_count = self.burned_count.saturating_add(1);
+            self.env().emit_event(
+                Transfer {
+                    from: Some(*owner),
+                    to: None,
+                    id: *id
+                }
+            );
+            Ok(())
+        }
+
+        fn remove_token_internal(&mut self, owner: &AccountId, id: &TokenId
+        ) -> Erc721Result {
+            let count = self.owned_tokens_count
+                .get(owner)
+                .ok_or(Error::StorageDataInconsistency)?;
+            let new_count = count.checked_sub(1).ok_or(Error::ReachingMin)?;
+            self.detach_token_internal(owner, id, new_count)?;
+            self.owned_tokens_count.insert(owner, &new_count);
+            Ok(())
+        }
+
+        // new_count is the owner's token count after the removal,
+        // the caller stores it.
+        fn detach_token_internal(
+            &mut self, owner: &AccountId, id: &TokenId, new_count: u32
+        ) -> Erc721Result {
+            // !!!!!!!!!!!!!!!!!!!!!!!!!
+            // approval is removed here.
+            if self.token_approvals.contains(&id) {
+                self.token_approvals.remove(&id);
+            }
+
+            let index = self.owned_tokens_index
+                .get(id)
+                .ok_or(Error::StorageDataInconsistency)?;
+            // move owner's last token into the gap
+            if index != new_count {
+                let last_id = self.owned_tokens
+                    .get((owner, new_count))
+                    .ok_or(Error::StorageDataInconsistency)?;
+                self.owned_tokens.insert((owner, index), &last_id);
+                self.owned_tokens_index.insert(last_id, &index);
+            }
+            self.owned_tokens.remove((owner, new_count));
+            self.owned_tokens_index.remove(id);
+            self.token_owners.remove(&id);
+            // user role is removed here.
+            if self.token_users.contains(id) {
+                self.token_users.remove(id);
+                self.env().emit_event(
+                    UpdateUser {
+                        id: *id,
+                        user: None,
+                        expires: 0
+                    }
+                );
+            }
+            // listing is removed here, seller does not own it anymore.
+            if self.remove_listing_internal(id)? {
+                self.env().emit_event(
+                    Delisted {
+                        id: *id,
+                        seller: *owner
+                    }
+                );
+            }
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn set_attribute_updater(&mut self, account: AccountId, allowed: bool
+        ) -> Erc721Result {
+            if self.env().caller() != self.founder {
+                return Err(Error::FounderOnly)
+            }
+
+            if allowed {
+                self.attribute_updaters.insert(account, &());
+            } else {
+                self.attribute_updaters.remove(account);
+            }
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_attribute_updater(&self, account: AccountId) -> bool {
+            self.attribute_updaters.contains(account)
+        }
+
+        // None removes the attribute.
+        #[ink(message)]
+        pub fn set_attribute(
+            &mut self, id: TokenId, key: AttributeKey, value: Option<AttributeValue>
+        ) -> Erc721Result {
+            let caller = self.env().caller();
+            if caller != self.founder && !self.attribute_updaters.contains(caller) {
+                return Err(Error::NotAttributeUpdater)
+            }
+            if !self.token_owners.contains(id) {
+                return Err(Error::TokenNotFound)
+            }
+            if key.len() > MAX_ATTRIBUTE_KEY_LEN {
+                return Err(Error::AttributeTooLarge)
+            }
+
+            let mut keys = self.token_attribute_keys.get(id).unwrap_or_default();
+            let position = keys.iter().position(|k| *k == key);
+            match &value {
+                Some(v) => {
+                    if v.len() > MAX_ATTRIBUTE_VALUE_LEN {
+                        return Err(Error::AttributeTooLarge)
+                    }
+                    if position.is_none() {
+                        if keys.len() >= MAX_ATTRIBUTES_PER_TOKEN {
+                            return Err(Error::TooManyAttributes)
+                        }
+                        keys.push(key.clone());
+                        self.token_attribute_keys.insert(id, &keys);
+                    }
+                    self.token_attributes.insert((id, key.clone()), v);
+                },
+                None => {
+                    match position {
+                        Some(i) => { keys.remove(i); },
+                        None => return Ok(())
+                    }
+                    self.token_attribute_keys.insert(id, &keys);
+                    self.token_attributes.remove((id, key.clone()));
+                }
+            }
+            self.env().emit_event(
+                AttributeChanged {
+                    id,
+                    key,
+                    value
+                }
+            );
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn attributes(&self, id: TokenId) -> AttributeList {
+            self.token_attribute_keys
+                .get(id)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|k| {
+                    let v = self.token_attributes.get((id, k.clone()))?;
+                    Some((k, v))
+                })
+                .collect()
+        }
+
+        #[ink(message)]
+        pub fn attribute(&self, id: TokenId, key: AttributeKey) -> Option<AttributeValue> {
+            self.token_attributes.get((id, key))
+        }
+
+        fn remove_attributes_internal(&mut self, id: &TokenId) {
+            if let Some(keys) = self.token_attribute_keys.get(id) {
+                for key in keys.into_iter() {
+                    self.token_attributes.remove((*id, key));
+                }
+                self.token_attribute_keys.remove(id);
+            }
+        }
+
+        // lends the token to user until expires, None takes it back.
+        #[ink(message)]
+        pub fn set_user(
+            &mut self,
+            gen: TokenGen,
+            phrase: TokenPhrase,
+            user: Option<AccountId>,
+            expires: Timestamp
         ) -> Erc721Result {
-            let caller = self.env().caller();
-            if caller == self.burn_account {
-                return Err(Error::AccountToBurn)
+            let id = self.string_to_hash_internal(&gen);
+            self.check_owner_phrase_internal(&id, &phrase)?;
+
+            match user {
+                Some(u) => {
+                    if u == self.burn_account {
+                        return Err(Error::AccountToBurn)
+                    }
+                    if expires <= self.env().block_timestamp() {
+                        return Err(Error::InvalidUserExpiry)
+                    }
+                    self.token_users.insert(id, &(u, expires));
+                },
+                None => {
+                    self.token_users.remove(id);
+                }
+            }
+            self.env().emit_event(
+                UpdateUser {
+                    id,
+                    user,
+                    expires: if user.is_some() { expires } else { 0 }
+                }
+            );
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn user_of(&self, id: TokenId) -> Option<AccountId> {
+            match self.token_users.get(id) {
+                Some((user, expires)) if self.env().block_timestamp() < expires => Some(user),
+                _ => None
             }
+        }
 
-            if self.env().transferred_value() < MINT_FEE {
-                return Err(Error::NotEnoughFee)
-            } 
-            
+        // 0 when the token has no user.
+        #[ink(message)]
+        pub fn user_expires(&self, id: TokenId) -> Timestamp {
+            self.token_users.get(id).map(|(_, expires)| expires).unwrap_or(0)
+        }
 
+        #[ink(message)]
+        pub fn list(
+            &mut self,
+            gen: TokenGen,
+            phrase: TokenPhrase,
+            price: Balance,
+            expires_at: Timestamp
+        ) -> Erc721Result {
             let id = self.string_to_hash_internal(&gen);
-            let secret = self.string_to_hash_internal(&phrase);
-            if self.token_owners.contains(&id) {
-                return Err(Error::TokenAlreadyExists)
+            let seller = self.check_owner_phrase_internal(&id, &phrase)?;
+            self.check_not_locked_internal(&id)?;
+            // price has to cover the contract fee taken from the sale
+            if price < self.fees.transfer || expires_at <= self.env().block_timestamp() {
+                return Err(Error::InvalidListing)
+            }
+
+            if !self.listings.contains(id) {
+                let index = self.listing_count;
+                self.listing_count = index.checked_add(1).ok_or(Error::ReachingMax)?;
+                self.listed_tokens.insert(index, &id);
+                self.listed_tokens_index.insert(id, &index);
+            }
+            self.listings.insert(id, &Listing {
+                seller,
+                price,
+                expires_at
+            });
+            self.env().emit_event(
+                Listed {
+                    id,
+                    seller,
+                    price,
+                    expires_at
+                }
+            );
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn cancel_listing(&mut self, id: TokenId) -> Erc721Result {
+            let listing = self.listings.get(id).ok_or(Error::NotListed)?;
+            if self.env().caller() != listing.seller {
+                return Err(Error::NotSeller)
+            }
+
+            self.remove_listing_internal(&id)?;
+            self.env().emit_event(
+                Delisted {
+                    id,
+                    seller: listing.seller
+                }
+            );
+            Ok(())
+        }
+
+        // seller gets the price minus royalty and the transfer fee,
+        // anything paid above the price goes back to the buyer.
+        #[ink(message, payable)]
+        pub fn buy(&mut self, id: TokenId) -> Erc721Result {
+            let buyer = self.env().caller();
+            let listing = self.listings.get(id).ok_or(Error::NotListed)?;
+            if self.env().block_timestamp() >= listing.expires_at {
+                return Err(Error::ListingExpired)
             }
-            if self.token_secrets.contains(&secret) {
+            if self.owner_of_internal(&id) != Some(listing.seller) {
                 return Err(Error::StorageDataInconsistency)
             }
-            
-            self.add_new_token_to_internal(&caller, &id)?;
-            self.token_secrets.insert(&id, &secret);
+            if buyer == listing.seller {
+                return Err(Error::TrasnferToSelf)
+            }
+            if buyer == self.burn_account {
+                return Err(Error::AccountToBurn)
+            }
+            let paid = self.env().transferred_value();
+            if paid < listing.price {
+                return Err(Error::NotEnoughFee)
+            }
+
+            self.remove_listing_internal(&id)?;
+            self.transfer_token_from_internal(&listing.seller, &buyer, &id)?;
             self.env().emit_event(
                 Transfer {
-                    from: Some(self.burn_account),
-                    to: Some(caller),
+                    from: Some(listing.seller),
+                    to: Some(buyer),
+                    id
+                }
+            );
+            self.env().emit_event(
+                Sold {
                     id,
+                    seller: listing.seller,
+                    buyer,
+                    price: listing.price
                 }
             );
-            Ok(())
+
+            self.pay_sale_proceeds_internal(&listing.seller, &id, listing.price)?;
+            self.pay_out_internal(&buyer, paid - listing.price)
         }
- 
-        fn string_to_hash_internal(&self, gen: &TokenGen) -> TokenId {
-            let input = gen.as_bytes();
-            let mut output 
-                = <GenHashing as ink_env::hash::HashOutput>::Type::default();
-            ink_env::hash_bytes::<GenHashing>(input, &mut output);
-            TokenId::from(output)
+
+        // splits a sale price into royalty, contract fee and seller's part.
+        fn pay_sale_proceeds_internal(
+            &self, seller: &AccountId, id: &TokenId, price: Balance
+        ) -> Erc721Result {
+            // contract fee first, royalty only from what is left of the price
+            let fee = self.fees.transfer.min(price);
+            let (royalty_receiver, royalty) = self.royalty_info_internal(id, price - fee);
+            self.pay_out_internal(seller, price - fee - royalty)?;
+            self.pay_out_internal(&royalty_receiver, royalty)
         }
 
-        fn add_new_token_to_internal(&mut self, to: &AccountId, id: &TokenId
+        #[ink(message)]
+        pub fn start_english_auction(
+            &mut self,
+            gen: TokenGen,
+            phrase: TokenPhrase,
+            reserve: Balance,
+            end: Timestamp
         ) -> Erc721Result {
-            let count = self.owned_tokens_count.get(to).unwrap_or(0);
-            let new_count = count.checked_add(1).ok_or(Error::ReachingMax)?;
-            
-            self.owned_tokens_count.insert(to, &new_count);
-            self.token_owners.insert(id, to);
-            Ok(())
+            let id = self.string_to_hash_internal(&gen);
+            let now = self.env().block_timestamp();
+            if end <= now {
+                return Err(Error::InvalidAuction)
+            }
+
+            self.start_auction_internal(
+                &id, &phrase, AuctionKind::English { reserve }, now, end
+            )
         }
 
-        #[ink(message, payable)]
-        pub fn burn(&mut self, gen: TokenGen, phrase: TokenPhrase
+        #[ink(message)]
+        pub fn start_dutch_auction(
+            &mut self,
+            gen: TokenGen,
+            phrase: TokenPhrase,
+            start_price: Balance,
+            end_price: Balance,
+            duration: Timestamp
         ) -> Erc721Result {
-            let caller = self.env().caller();
             let id = self.string_to_hash_internal(&gen);
-            let secret = self.string_to_hash_internal(&phrase);
-            let owner = self.token_owners.get(&id).ok_or(Error::TokenNotFound)?;
-            if owner != caller {
-                return Err(Error::NotTokenOwner)
+            let now = self.env().block_timestamp();
+            if start_price < end_price || duration == 0 {
+                return Err(Error::InvalidAuction)
             }
-            let stored_secret = 
-                self.token_secrets.get(&id).ok_or(Error::StorageDataInconsistency)?;
-            if secret != stored_secret {
-                return Err(Error::NotTokenOwner)
+            let end = now.checked_add(duration).ok_or(Error::InvalidAuction)?;
+
+            self.start_auction_internal(
+                &id, &phrase, AuctionKind::Dutch { start_price, end_price }, now, end
+            )
+        }
+
+        fn start_auction_internal(
+            &mut self,
+            id: &TokenId,
+            phrase: &TokenPhrase,
+            kind: AuctionKind,
+            start: Timestamp,
+            end: Timestamp
+        ) -> Erc721Result {
+            let seller = self.check_owner_phrase_internal(id, phrase)?;
+
+            // escrow the token, this also drops its listing and approvals
+            let contract = self.env().account_id();
+            self.transfer_token_from_internal(&seller, &contract, id)?;
+            self.auctions.insert(id, &Auction {
+                seller,
+                kind,
+                start,
+                end,
+                highest_bidder: None,
+                highest_bid: 0
+            });
+            self.env().emit_event(
+                Transfer {
+                    from: Some(seller),
+                    to: Some(contract),
+                    id: *id
+                }
+            );
+            self.env().emit_event(
+                AuctionStarted {
+                    id: *id,
+                    seller,
+                    kind,
+                    end
+                }
+            );
+            Ok(())
+        }
+
+        // english: escrows the bid, outbid one can be withdrawn later.
+        // dutch: first bid paying the current price wins right away.
+        #[ink(message, payable)]
+        pub fn bid(&mut self, id: TokenId) -> Erc721Result {
+            let bidder = self.env().caller();
+            let amount = self.env().transferred_value();
+            let mut auction = self.auctions.get(id).ok_or(Error::AuctionNotFound)?;
+            if self.env().block_timestamp() >= auction.end {
+                return Err(Error::AuctionEnded)
+            }
+            if bidder == auction.seller {
+                return Err(Error::TrasnferToSelf)
+            }
+            if bidder == self.burn_account {
+                return Err(Error::AccountToBurn)
             }
 
-            if self.env().transferred_value() < BURN_FEE {
-                return Err(Error::NotEnoughFee)
+            match auction.kind {
+                AuctionKind::English { reserve } => {
+                    if amount < reserve || amount <= auction.highest_bid {
+                        return Err(Error::BidTooLow)
+                    }
+
+                    self.escrowed_funds = self.escrowed_funds
+                        .checked_add(amount)
+                        .ok_or(Error::ReachingMax)?;
+                    if let Some(outbid) = auction.highest_bidder {
+                        let returns = self.pending_returns.get(outbid).unwrap_or(0);
+                        self.pending_returns.insert(outbid, &(returns + auction.highest_bid));
+                    }
+                    auction.highest_bidder = Some(bidder);
+                    auction.highest_bid = amount;
+                    self.auctions.insert(id, &auction);
+                    self.env().emit_event(
+                        BidPlaced {
+                            id,
+                            bidder,
+                            amount
+                        }
+                    );
+                    Ok(())
+                },
+                AuctionKind::Dutch { .. } => {
+                    let price = self.current_price_internal(&auction);
+                    if amount < price {
+                        return Err(Error::BidTooLow)
+                    }
+
+                    self.env().emit_event(
+                        BidPlaced {
+                            id,
+                            bidder,
+                            amount: price
+                        }
+                    );
+                    self.finish_auction_internal(&id, &auction, Some(bidder), price)?;
+                    self.pay_out_internal(&bidder, amount - price)
+                }
             }
+        }
 
-            self.remove_token_internal(&owner, &id)?;
-            // !!!!!!!!!!!!!!!!!!!!!!!
-            // secret is removed here.
-            self.token_secrets.remove(&id);
+        // anyone can settle after the end, token goes to the winner
+        // or back to the seller when nobody bid.
+        #[ink(message)]
+        pub fn settle(&mut self, id: TokenId) -> Erc721Result {
+            let auction = self.auctions.get(id).ok_or(Error::AuctionNotFound)?;
+            if self.env().block_timestamp() < auction.end {
+                return Err(Error::AuctionNotEnded)
+            }
+
+            if auction.highest_bidder.is_some() {
+                self.escrowed_funds -= auction.highest_bid;
+            }
+            self.finish_auction_internal(
+                &id, &auction, auction.highest_bidder, auction.highest_bid
+            )
+        }
+
+        fn finish_auction_internal(
+            &mut self,
+            id: &TokenId,
+            auction: &Auction,
+            winner: Option<AccountId>,
+            price: Balance
+        ) -> Erc721Result {
+            let contract = self.env().account_id();
+            let to = winner.unwrap_or(auction.seller);
+            self.auctions.remove(id);
+            self.transfer_token_from_internal(&contract, &to, id)?;
             self.env().emit_event(
                 Transfer {
-                    from: Some(caller),
-                    to: Some(self.burn_account),
-                    id
+                    from: Some(contract),
+                    to: Some(to),
+                    id: *id
+                }
+            );
+            self.env().emit_event(
+                AuctionSettled {
+                    id: *id,
+                    seller: auction.seller,
+                    winner,
+                    price
                 }
             );
+
+            if winner.is_some() {
+                self.pay_sale_proceeds_internal(&auction.seller, id, price)?;
+            }
             Ok(())
         }
 
-        fn remove_token_internal(&mut self, owner: &AccountId, id: &TokenId
-        ) -> Erc721Result {
-            // !!!!!!!!!!!!!!!!!!!!!!!!!
-            // approval is removed here.
-            if self.token_approvals.contains(&id) {
-                self.token_approvals.remove(&id);
+        #[ink(message)]
+        pub fn auction(&self, id: TokenId) -> Option<Auction> {
+            self.auctions.get(id)
+        }
+
+        // dutch auctions only, english ones return the highest bid.
+        #[ink(message)]
+        pub fn current_price(&self, id: TokenId) -> Option<Balance> {
+            self.auctions.get(id).map(|a| self.current_price_internal(&a))
+        }
+
+        fn current_price_internal(&self, auction: &Auction) -> Balance {
+            match auction.kind {
+                AuctionKind::English { .. } => auction.highest_bid,
+                AuctionKind::Dutch { start_price, end_price } => {
+                    let now = self.env().block_timestamp();
+                    if now >= auction.end {
+                        return end_price
+                    }
+                    let elapsed = now.saturating_sub(auction.start) as Balance;
+                    let duration = (auction.end - auction.start) as Balance;
+                    let drop = start_price - end_price;
+                    start_price - Self::mul_div_internal(drop, elapsed, duration)
+                }
             }
+        }
 
-            let count = self.owned_tokens_count
-                .get(owner)
+        #[ink(message)]
+        pub fn pending_returns(&self, account: AccountId) -> Balance {
+            self.pending_returns.get(account).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        pub fn withdraw_pending_returns(&mut self) -> Erc721Result {
+            let caller = self.env().caller();
+            let amount = self.pending_returns.get(caller).unwrap_or(0);
+            if amount == 0 {
+                return Err(Error::NoPendingReturns)
+            }
+
+            self.pending_returns.remove(caller);
+            self.escrowed_funds -= amount;
+            self.pay_out_internal(&caller, amount)
+        }
+
+        #[ink(message)]
+        pub fn listing(&self, id: TokenId) -> Option<Listing> {
+            self.listings.get(id)
+        }
+
+        // expired listings are skipped, so a page can be shorter than limit.
+        #[ink(message)]
+        pub fn active_listings(&self, offset: u32, limit: u32) -> ListingList {
+            let now = self.env().block_timestamp();
+            let end = offset.saturating_add(limit).min(self.listing_count);
+            let mut list = ink_prelude::vec![];
+            for i in offset..end {
+                if let Some(id) = self.listed_tokens.get(i) {
+                    if let Some(l) = self.listings.get(id) {
+                        if now < l.expires_at {
+                            list.push((id, l));
+                        }
+                    }
+                }
+            }
+            list
+        }
+
+        // returns false if the token was not listed.
+        fn remove_listing_internal(&mut self, id: &TokenId) -> Result<bool, Error> {
+            if !self.listings.contains(id) {
+                return Ok(false)
+            }
+
+            let index = self.listed_tokens_index
+                .get(id)
                 .ok_or(Error::StorageDataInconsistency)?;
-            let new_count = count.checked_sub(1).ok_or(Error::ReachingMin)?;
-            self.owned_tokens_count.insert(owner, &new_count);
-            self.token_owners.remove(&id);
+            let last = self.listing_count.checked_sub(1).ok_or(Error::ReachingMin)?;
+            if index != last {
+                let last_id = self.listed_tokens
+                    .get(last)
+                    .ok_or(Error::StorageDataInconsistency)?;
+                self.listed_tokens.insert(index, &last_id);
+                self.listed_tokens_index.insert(last_id, &index);
+            }
+            self.listed_tokens.remove(last);
+            self.listed_tokens_index.remove(id);
+            self.listings.remove(id);
+            self.listing_count = last;
+            Ok(true)
+        }
+
+        #[inline]
+        fn pay_out_internal(&self, to: &AccountId, amount: Balance) -> Erc721Result {
+            if amount > 0 && self.env().transfer(*to, amount).is_err() {
+                return Err(Error::NotEnoughBalance)
+            }
             Ok(())
         }
 
@@ -347,7 +2533,26 @@ mod erc721 {
                 return Err(Error::NotTokenOwner)
             }
 
-            if to == self.burn_account {
+            self.approve_token_internal(&owner, &to, &id)
+        }
+
+        #[ink(message)]
+        pub fn approve_signed(
+            &mut self, to: AccountId, gen: TokenGen, signature: TokenSignature
+        ) -> Erc721Result {
+            let id = self.string_to_hash_internal(&gen);
+            self.check_signed_owner_internal(&id, &TokenAction::Approve(to), &signature)?;
+
+            let owner = self.env().caller();
+            self.approve_token_internal(&owner, &to, &id)?;
+            self.use_nonce_internal(&id);
+            Ok(())
+        }
+
+        fn approve_token_internal(
+            &mut self, owner: &AccountId, to: &AccountId, id: &TokenId
+        ) -> Erc721Result {
+            if *to == self.burn_account {
                 return Err(Error::AccountToBurn)
             }
 
@@ -355,32 +2560,85 @@ mod erc721 {
                 return Err(Error::ApproveForSelf)
             } 
 
-            if self.approve_for_token_internal(&to, &id).is_ok() {
+            self.check_not_locked_internal(id)?;
+
+            if self.approve_for_token_internal(to, id).is_ok() {
                 self.env().emit_event(
                     Approval {
-                        from: owner,
-                        to,
-                        id
+                        from: *owner,
+                        to: *to,
+                        id: *id,
+                        approved: true
                     }
                 );
             }
             Ok(())
         }
 
-        fn approve_for_token_internal(&mut self, to: &AccountId, id: &TokenId
-        ) -> InternalResult {
-            match self.token_approvals.get(id) {
-                Some(mut v) => {
-                    if !v.contains(to) {
-                        v.push(*to);
-                        self.token_approvals.insert(id, &v);
-                    } else {
-                        return Err(())
+        fn approve_for_token_internal(&mut self, to: &AccountId, id: &TokenId
+        ) -> InternalResult {
+            match self.token_approvals.get(id) {
+                Some(mut v) => {
+                    if !v.contains(to) {
+                        v.push(*to);
+                        self.token_approvals.insert(id, &v);
+                    } else {
+                        return Err(())
+                    }
+                },
+                None => {
+                    self.token_approvals.insert(id, &ink_prelude::vec![*to]);
+                }                
+            }
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn revoke_approval(
+            &mut self, operator: AccountId, gen: TokenGen, phrase: TokenPhrase
+        ) -> Erc721Result {
+            let id = self.string_to_hash_internal(&gen);
+            let owner = self.check_owner_phrase_internal(&id, &phrase)?;
+
+            let mut v = self.get_approved_internal(&id).ok_or(Error::NotApproved)?;
+            let idx = v.iter()
+                .position(|a| *a == operator)
+                .ok_or(Error::NotApproved)?;
+            // keep order, the last one is what get_approved_single returns
+            v.remove(idx);
+            if v.is_empty() {
+                self.token_approvals.remove(id);
+            } else {
+                self.token_approvals.insert(id, &v);
+            }
+            self.env().emit_event(
+                Approval {
+                    from: owner,
+                    to: operator,
+                    id,
+                    approved: false
+                }
+            );
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn clear_approvals(&mut self, gen: TokenGen, phrase: TokenPhrase
+        ) -> Erc721Result {
+            let id = self.string_to_hash_internal(&gen);
+            let owner = self.check_owner_phrase_internal(&id, &phrase)?;
+
+            let v = self.get_approved_internal(&id).unwrap_or_default();
+            self.token_approvals.remove(id);
+            for operator in v.into_iter() {
+                self.env().emit_event(
+                    Approval {
+                        from: owner,
+                        to: operator,
+                        id,
+                        approved: false
                     }
-                },
-                None => {
-                    self.token_approvals.insert(id, &ink_prelude::vec![*to]);
-                }                
+                );
             }
             Ok(())
         }
@@ -402,17 +2660,49 @@ mod erc721 {
                 if secret != stored_secret {
                     return Err(Error::NotTokenOwner)
                 }
+                self.check_not_locked_internal(&id)?;
+            }
+
+            self.set_approval_for_all_internal(&caller, &to, approved)
+        }
+
+        #[ink(message)]
+        pub fn set_approval_for_all_signed(
+            &mut self, to: AccountId, approved: bool, pairs: TokenGenSignaturePairs
+        ) -> Erc721Result {
+            let caller = self.env().caller();
+            if caller == to {
+                return Err(Error::ApproveForSelf)
+            }
+
+            let action = TokenAction::SetApprovalForAll(to, approved);
+            let mut ids = ink_prelude::vec![];
+            for (gen, sig) in pairs.iter() {
+                let id = self.string_to_hash_internal(gen);
+                self.check_signed_owner_internal(&id, &action, sig)?;
+                self.check_not_locked_internal(&id)?;
+                ids.push(id);
+            }
+
+            self.set_approval_for_all_internal(&caller, &to, approved)?;
+            for id in ids.iter() {
+                self.use_nonce_internal(id);
             }
+            Ok(())
+        }
 
-            if to == self.burn_account || caller == self.burn_account {
+        fn set_approval_for_all_internal(
+            &mut self, caller: &AccountId, to: &AccountId, approved: bool
+        ) -> Erc721Result {
+            if *to == self.burn_account || *caller == self.burn_account {
                 return Err(Error::AccountToBurn)
             }
 
-            if self.approve_for_all_internal(&caller, &to, approved).is_ok() {
+            if self.approve_for_all_internal(caller, to, approved).is_ok() {
                 self.env().emit_event(
                     ApprovalForAll {
-                        from: caller,
-                        to,
+                        from: *caller,
+                        to: *to,
                         approved
                     }
                 );
@@ -453,66 +2743,222 @@ mod erc721 {
                 return Err(Error::NotTokenOwner)
             }
 
+            self.transfer_owned_internal(&owner, &to, &id)
+        }
+
+        // all or nothing, the transfer fee is paid once per token.
+        #[ink(message, payable)]
+        pub fn transfer_batch(&mut self, to: AccountId, pairs: TokenGenPhrasePairs
+        ) -> Erc721Result {
+            let owner = self.env().caller();
             if owner == to {
                 return Err(Error::TrasnferToSelf)
             }
-
             if to == self.burn_account {
                 return Err(Error::AccountToBurn)
             }
 
-            if self.env().transferred_value() < TRANSFER_FEE {
-                return Err(Error::NotEnoughFee)
+            let size = u32::try_from(pairs.len()).map_err(|_| Error::ReachingMax)?;
+            let total_fee = self.fees.transfer
+                .checked_mul(size.into())
+                .ok_or(Error::ReachingMax)?;
+            let fee = self.required_fee_internal(total_fee)?;
+
+            let mut ids = TokenIdList::with_capacity(pairs.len());
+            for (gen, ph) in pairs.iter() {
+                let id = self.string_to_hash_internal(gen);
+                self.check_owner_phrase_internal(&id, ph)?;
+                self.check_not_locked_internal(&id)?;
+                if ids.contains(&id) {
+                    return Err(Error::DuplicateToken)
+                }
+                ids.push(id);
+            }
+            let from_count = self.balance_of_or_zero_internal(&owner);
+            let to_count = self.balance_of_or_zero_internal(&to);
+            let new_to_count = to_count.checked_add(size).ok_or(Error::ReachingMax)?;
+            // cannot underflow, every id is owned by the caller
+            let new_from_count = from_count - size;
+
+            for (i, id) in (0..size).zip(ids.iter()) {
+                self.detach_token_internal(&owner, id, from_count - i - 1)?;
+                self.attach_token_internal(&to, id, to_count + i);
+                self.env().emit_event(
+                    Transfer {
+                        from: Some(owner),
+                        to: Some(to),
+                        id: *id
+                    }
+                );
+            }
+            self.owned_tokens_count.insert(owner, &new_from_count);
+            self.owned_tokens_count.insert(to, &new_to_count);
+            self.refund_overpaid_internal(fee)
+        }
+
+        #[ink(message, payable)]
+        pub fn transfer_signed(
+            &mut self, to: AccountId, gen: TokenGen, signature: TokenSignature
+        ) -> Erc721Result {
+            let id = self.string_to_hash_internal(&gen);
+            self.check_signed_owner_internal(&id, &TokenAction::Transfer(to), &signature)?;
+
+            let owner = self.env().caller();
+            self.transfer_owned_internal(&owner, &to, &id)?;
+            self.use_nonce_internal(&id);
+            Ok(())
+        }
+
+        // transfer by the owner, after the owner was authorized.
+        fn transfer_owned_internal(
+            &mut self, owner: &AccountId, to: &AccountId, id: &TokenId
+        ) -> Erc721Result {
+            if owner == to {
+                return Err(Error::TrasnferToSelf)
+            }
+
+            if *to == self.burn_account {
+                return Err(Error::AccountToBurn)
             }
 
-            self.transfer_token_from_internal(&owner, &to, &id)?;
+            let fee = self.required_fee_internal(self.fees.transfer)?;
+
+            self.transfer_token_from_internal(owner, to, id)?;
             self.env().emit_event(
                 Transfer {
-                    from: Some(owner),
-                    to: Some(to),
-                    id
+                    from: Some(*owner),
+                    to: Some(*to),
+                    id: *id
                 }
             );
-            Ok(())
+            self.refund_overpaid_internal(fee)
         }
 
         #[ink(message, payable)]
         pub fn transfer_from(&mut self, from: AccountId, to: AccountId, gen: TokenGen
         ) -> Erc721Result {
             let id = self.string_to_hash_internal(&gen);
-            let owner = self.owner_of_internal(&id).ok_or(Error::TokenNotFound)?;
-            if owner != from {
+            self.transfer_from_internal(&from, &to, &id)
+        }
+
+        #[ink(message, payable)]
+        pub fn transfer_from_id(&mut self, from: AccountId, to: AccountId, id: TokenId
+        ) -> Erc721Result {
+            self.transfer_from_internal(&from, &to, &id)
+        }
+
+        fn transfer_from_internal(
+            &mut self, from: &AccountId, to: &AccountId, id: &TokenId
+        ) -> Erc721Result {
+            self.check_transfer_from_internal(from, to, id)?;
+            let fee = self.required_fee_internal(self.fees.transfer)?;
+            
+            self.transfer_token_from_internal(from, to, id)?;
+            self.env().emit_event(
+                Transfer {
+                    from: Some(*from),
+                    to: Some(*to),
+                    id: *id
+                }
+            );
+            self.refund_overpaid_internal(fee)
+        }
+
+        // same as transfer_from, but contract recipients have to accept the token.
+        #[ink(message, payable)]
+        pub fn safe_transfer_from(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            gen: TokenGen,
+            data: BytesVec
+        ) -> Erc721Result {
+            let id = self.string_to_hash_internal(&gen);
+            self.check_transfer_from_internal(&from, &to, &id)?;
+            let fee = self.required_fee_internal(self.fees.transfer)?;
+
+            self.transfer_token_from_internal(&from, &to, &id)?;
+            self.env().emit_event(
+                Transfer {
+                    from: Some(from),
+                    to: Some(to),
+                    id
+                }
+            );
+
+            // recipient already owns the token, a rejection reverts the whole call
+            let caller = self.env().caller();
+            self.transfer_acceptance_check_internal(&caller, &from, &to, &id, &data)?;
+            self.refund_overpaid_internal(fee)
+        }
+
+        fn check_transfer_from_internal(
+            &self, from: &AccountId, to: &AccountId, id: &TokenId
+        ) -> Erc721Result {
+            let owner = self.owner_of_internal(id).ok_or(Error::TokenNotFound)?;
+            if owner != *from {
                 return Err(Error::StorageDataInconsistency)
             }
             
             let caller = self.env().caller();
-            if owner != caller {
-                if !self.is_approved_internal(&owner, &id, &caller) {
-                    return Err(Error::NotApproved)
-                }
+            if owner != caller && !self.is_approved_internal(&owner, id, &caller) {
+                return Err(Error::NotApproved)
             } // then caller is owner or approved one
 
             if from == to {
                 return Err(Error::TrasnferToSelf)
             }
 
-            if to == self.burn_account {
+            if *to == self.burn_account {
                 return Err(Error::AccountToBurn)
             }
+            Ok(())
+        }
 
-            if self.env().transferred_value() < TRANSFER_FEE {
-                return Err(Error::NotEnoughFee)
-            }
-            
-            self.transfer_token_from_internal(&from, &to, &id)?;
-            self.env().emit_event(
-                Transfer {
-                    from: Some(from),
-                    to: Some(to),
-                    id
+        // reentry stays off, contract fields are only written back
+        // when the message returns.
+        fn transfer_acceptance_check_internal(
+            &self,
+            operator: &AccountId,
+            from: &AccountId,
+            to: &AccountId,
+            id: &TokenId,
+            data: &BytesVec
+        ) -> Erc721Result {
+            use ink_env::call;
+
+            let ret = call::build_call::<Environment>()
+                .call_type(call::Call::new().callee(*to))
+                .exec_input(
+                    call::ExecutionInput::new(
+                        call::Selector::new(ON_ERC721_RECEIVED_SELECTOR)
+                    )
+                    .push_arg(operator)
+                    .push_arg(from)
+                    .push_arg(id)
+                    .push_arg(data)
+                )
+                .returns::<BytesVec>()
+                .fire();
+            match ret {
+                Ok(v) => {
+                    if v[..] != ON_ERC721_RECEIVED_SELECTOR[..] {
+                        return Err(Error::TransferDenied)
+                    }
+                    Ok(())
+                },
+                Err(e) => {
+                    match e {
+                        // plain accounts have no code to call
+                        ink_env::Error::CodeNotFound | ink_env::Error::NotCallable => {
+                            Ok(())
+                        },
+                        _ => {
+                            Err(Error::TransferDenied)
+                        }
+                    }
                 }
-            );
-            Ok(())
+            }
         }
 
         fn transfer_token_from_internal(
@@ -521,6 +2967,7 @@ mod erc721 {
             to: &AccountId,
             id: &TokenId
         ) -> Erc721Result {
+            self.check_not_locked_internal(id)?;
             self.remove_token_internal(from, id)?;
             self.add_new_token_to_internal(to, id)?;
             Ok(())
@@ -531,13 +2978,747 @@ mod erc721 {
     #[cfg(test)]
     mod tests {
         
-        //use super::*;
+        use super::*;
 
         use ink_lang as ink;
 
+        type Env = ink_env::DefaultEnvironment;
+
+        fn accounts() -> ink_env::test::DefaultAccounts<Env> {
+            ink_env::test::default_accounts::<Env>()
+        }
+
+        fn set_caller(account: AccountId) {
+            ink_env::test::set_caller::<Env>(account);
+        }
+
+        fn set_value(value: Balance) {
+            ink_env::test::set_value_transferred::<Env>(value);
+        }
+
+        fn s(value: &str) -> ink_prelude::string::String {
+            ink_prelude::string::String::from(value)
+        }
+
+        // alice is the founder, frank the contract account.
+        fn new_contract() -> Erc721 {
+            let accounts = accounts();
+            ink_env::test::set_callee::<Env>(accounts.frank);
+            ink_env::test::set_account_balance::<Env>(accounts.frank, 0);
+            set_caller(accounts.alice);
+            Erc721::new(s("name"), s("symbol"))
+        }
+
+        // recoverable ecdsa signature as ink_env::ecdsa_recover expects it.
+        fn sign(secret: &secp256k1::SecretKey, message: &[u8; 32]) -> TokenSignature {
+            let secp = secp256k1::Secp256k1::new();
+            let signature = secp.sign_ecdsa_recoverable(
+                &secp256k1::Message::from_slice(message).unwrap(),
+                secret
+            );
+            let (recovery_id, bytes) = signature.serialize_compact();
+            let mut out = [0u8; 65];
+            out[..64].copy_from_slice(&bytes);
+            out[64] = recovery_id.to_i32() as u8;
+            out
+        }
+
+        fn public_key(secret: &secp256k1::SecretKey) -> TokenPublicKey {
+            let secp = secp256k1::Secp256k1::new();
+            secp256k1::PublicKey::from_secret_key(&secp, secret).serialize()
+        }
+
+        fn sign_token_action(
+            erc721: &Erc721,
+            secret: &secp256k1::SecretKey,
+            id: TokenId,
+            action: TokenAction
+        ) -> TokenSignature {
+            let mut message = [0u8; 32];
+            ink_env::hash_encoded::<GenHashing, _>(
+                &(accounts().frank, id, action, erc721.token_nonce(id)),
+                &mut message
+            );
+            sign(secret, &message)
+        }
+
         #[ink::test]
         fn it_works() {
  
         }
+
+        #[ink::test]
+        fn signed_approval_for_all_needs_owner() {
+            let accounts = accounts();
+            let mut erc721 = new_contract();
+            set_value(DEFAULT_MINT_FEE);
+            erc721.mint(s("a"), s("a")).unwrap();
+            let key = secp256k1::SecretKey::from_slice(&[0xcd; 32]).unwrap();
+            erc721.set_token_key(s("a"), s("a"), Some(public_key(&key))).unwrap();
+            let id = erc721.token_id(s("a"));
+            let signature = sign_token_action(
+                &erc721, &key, id, TokenAction::SetApprovalForAll(accounts.bob, true)
+            );
+            let signed = ink_prelude::vec![(s("a"), signature)];
+
+            // replayed from the mempool by someone else
+            set_caller(accounts.charlie);
+            assert_eq!(
+                erc721.set_approval_for_all_signed(accounts.bob, true, signed.clone()),
+                Err(Error::NotTokenOwner)
+            );
+            assert_eq!(erc721.token_nonce(id), 0);
+
+            set_caller(accounts.alice);
+            erc721.set_approval_for_all_signed(accounts.bob, true, signed.clone()).unwrap();
+            assert_eq!(erc721.token_nonce(id), 1);
+            assert!(erc721.is_approved_for_all(accounts.alice, accounts.bob));
+            assert_eq!(
+                erc721.set_approval_for_all_signed(accounts.bob, true, signed),
+                Err(Error::InvalidSignature)
+            );
+        }
+
+        #[ink::test]
+        fn commit_reveal_only_closes_direct_mints() {
+            let accounts = accounts();
+            let mut erc721 = new_contract();
+            let salt = Hash::from([7; 32]);
+            let commitment = erc721.mint_commitment(s("gen"), s("phrase"), salt);
+            set_value(DEFAULT_MINT_FEE);
+            assert_eq!(erc721.commit_mint(commitment), Err(Error::CommitRevealDisabled));
+
+            erc721.set_commit_reveal_only(true).unwrap();
+            set_caller(accounts.bob);
+            erc721.commit_mint(commitment).unwrap();
+            for _ in 0..MIN_REVEAL_DELAY {
+                ink_env::test::advance_block::<Env>();
+            }
+
+            // the gen is public once the reveal is in the mempool
+            set_caller(accounts.charlie);
+            assert_eq!(erc721.mint(s("gen"), s("x")), Err(Error::CommitRevealRequired));
+            assert_eq!(
+                erc721.mint_batch(ink_prelude::vec![(s("gen"), s("x"))]),
+                Err(Error::CommitRevealRequired)
+            );
+
+            set_caller(accounts.bob);
+            set_value(0);
+            erc721.reveal_mint(s("gen"), s("phrase"), salt).unwrap();
+            assert_eq!(erc721.owner_of(s("gen")), Some(accounts.bob));
+        }
+
+        #[ink::test]
+        fn copied_commitment_does_not_block_committer() {
+            let accounts = accounts();
+            let mut erc721 = new_contract();
+            erc721.set_commit_reveal_only(true).unwrap();
+            let salt = Hash::from([7; 32]);
+            let commitment = erc721.mint_commitment(s("gen"), s("phrase"), salt);
+
+            set_value(DEFAULT_MINT_FEE);
+            set_caller(accounts.charlie);
+            erc721.commit_mint(commitment).unwrap();
+            set_caller(accounts.bob);
+            erc721.commit_mint(commitment).unwrap();
+            assert_eq!(erc721.commit_mint(commitment), Err(Error::CommitmentAlreadyExists));
+            for _ in 0..MIN_REVEAL_DELAY {
+                ink_env::test::advance_block::<Env>();
+            }
+
+            set_value(0);
+            erc721.reveal_mint(s("gen"), s("phrase"), salt).unwrap();
+            assert_eq!(erc721.owner_of(s("gen")), Some(accounts.bob));
+            assert!(erc721.pending_mint(accounts.bob, commitment).is_none());
+            assert!(erc721.pending_mint(accounts.charlie, commitment).is_some());
+        }
+
+        fn pairs(gens: &[&str]) -> TokenGenPhrasePairs {
+            gens.iter().map(|g| (s(g), s(g))).collect()
+        }
+
+        fn ids(erc721: &Erc721, gens: &[&str]) -> TokenIdList {
+            gens.iter().map(|g| erc721.token_id(s(g))).collect()
+        }
+
+        #[ink::test]
+        fn batch_transfer_keeps_owner_indexes() {
+            let accounts = accounts();
+            let mut erc721 = new_contract();
+            let gens = ["a", "b", "c", "d", "e"];
+            set_value(DEFAULT_MINT_FEE * 2);
+            assert_eq!(erc721.mint_batch(pairs(&["a", "a"])), Err(Error::DuplicateToken));
+            set_value(DEFAULT_MINT_FEE * 5);
+            erc721.mint_batch(pairs(&gens)).unwrap();
+            assert_eq!(erc721.tokens_of_owner(accounts.alice, 0, 10), ids(&erc721, &gens));
+            set_value(DEFAULT_MINT_FEE);
+            erc721.mint(s("x"), s("x")).unwrap();
+            set_caller(accounts.bob);
+            erc721.mint(s("y"), s("y")).unwrap();
+
+            set_caller(accounts.alice);
+            set_value(DEFAULT_TRANSFER_FEE * 2);
+            assert_eq!(
+                erc721.transfer_batch(accounts.bob, pairs(&["b", "b"])),
+                Err(Error::DuplicateToken)
+            );
+            erc721.transfer_batch(accounts.bob, pairs(&["b", "d"])).unwrap();
+
+            // b is swapped out by x (last), then d by e (last after that)
+            assert_eq!(erc721.balance_of(accounts.alice), 4);
+            assert_eq!(
+                erc721.tokens_of_owner(accounts.alice, 0, 10),
+                ids(&erc721, &["a", "x", "c", "e"])
+            );
+            assert_eq!(erc721.balance_of(accounts.bob), 3);
+            assert_eq!(
+                erc721.tokens_of_owner(accounts.bob, 0, 10),
+                ids(&erc721, &["y", "b", "d"])
+            );
+            for (owner, count) in [(accounts.alice, 4), (accounts.bob, 3)] {
+                for index in 0..count {
+                    let id = erc721.token_of_owner_by_index(owner, index).unwrap();
+                    assert_eq!(erc721.owner_of_id(id), Some(owner));
+                }
+                assert_eq!(erc721.token_of_owner_by_index(owner, count), None);
+            }
+
+            // indexes stay usable for single transfers afterwards
+            set_value(DEFAULT_TRANSFER_FEE);
+            erc721.transfer(accounts.bob, s("a"), s("a")).unwrap();
+            assert_eq!(
+                erc721.tokens_of_owner(accounts.alice, 0, 10),
+                ids(&erc721, &["e", "x", "c"])
+            );
+        }
+
+        #[ink::test]
+        fn open_phase_closes_other_mints() {
+            let accounts = accounts();
+            let mut erc721 = new_contract();
+            erc721.add_mint_phase(MintPhase {
+                start: 0,
+                end: 60,
+                price: 10,
+                wallet_limit: 1,
+                merkle_root: Some(Hash::from([1; 32]))
+            }).unwrap();
+
+            set_caller(accounts.bob);
+            set_value(DEFAULT_MINT_FEE);
+            assert_eq!(erc721.mint(s("a"), s("a")), Err(Error::MintPhaseActive));
+            assert_eq!(erc721.mint_batch(pairs(&["a"])), Err(Error::MintPhaseActive));
+            assert_eq!(
+                erc721.mint_with_royalty(s("a"), s("a"), Royalty {
+                    receiver: accounts.bob,
+                    rate: 0
+                }),
+                Err(Error::MintPhaseActive)
+            );
+            assert_eq!(
+                erc721.mint_with_proof(s("a"), s("a"), ink_prelude::vec![]),
+                Err(Error::InvalidMerkleProof)
+            );
+
+            while ink_env::block_timestamp::<Env>() < 60 {
+                ink_env::test::advance_block::<Env>();
+            }
+            assert_eq!(erc721.current_phase(), None);
+            assert_eq!(
+                erc721.mint_with_proof(s("a"), s("a"), ink_prelude::vec![]),
+                Err(Error::NoActiveMintPhase)
+            );
+            erc721.mint(s("a"), s("a")).unwrap();
+        }
+
+        fn balance(account: AccountId) -> Balance {
+            ink_env::test::get_account_balance::<Env>(account).unwrap()
+        }
+
+        // the off-chain engine does not credit transferred value to the callee.
+        fn set_contract_balance(value: Balance) {
+            ink_env::test::set_account_balance::<Env>(accounts().frank, value);
+        }
+
+        fn advance_to(timestamp: Timestamp) {
+            while ink_env::block_timestamp::<Env>() < timestamp {
+                ink_env::test::advance_block::<Env>();
+            }
+        }
+
+        #[ink::test]
+        fn english_auction_refunds_outbid_and_pays_seller() {
+            let accounts = accounts();
+            let mut erc721 = new_contract();
+            set_value(DEFAULT_MINT_FEE);
+            erc721.mint(s("a"), s("a")).unwrap();
+            set_value(0);
+            let start = ink_env::block_timestamp::<Env>();
+            erc721.start_english_auction(s("a"), s("a"), 100, start + 60).unwrap();
+            let id = erc721.token_id(s("a"));
+            assert_eq!(erc721.owner_of_id(id), Some(accounts.frank));
+
+            set_caller(accounts.bob);
+            set_value(50);
+            assert_eq!(erc721.bid(id), Err(Error::BidTooLow));
+            set_value(200);
+            erc721.bid(id).unwrap();
+            set_caller(accounts.charlie);
+            assert_eq!(erc721.bid(id), Err(Error::BidTooLow));
+            set_value(300);
+            erc721.bid(id).unwrap();
+            assert_eq!(erc721.pending_returns(accounts.bob), 200);
+            assert_eq!(erc721.escrowed_funds, 500);
+            set_contract_balance(500);
+
+            set_value(0);
+            assert_eq!(erc721.settle(id), Err(Error::AuctionNotEnded));
+            set_caller(accounts.bob);
+            let bob_before = balance(accounts.bob);
+            erc721.withdraw_pending_returns().unwrap();
+            assert_eq!(balance(accounts.bob) - bob_before, 200);
+            assert_eq!(erc721.withdraw_pending_returns(), Err(Error::NoPendingReturns));
+            assert_eq!(erc721.escrowed_funds, 300);
+
+            advance_to(start + 60);
+            set_value(400);
+            assert_eq!(erc721.bid(id), Err(Error::AuctionEnded));
+            set_value(0);
+            let seller_before = balance(accounts.alice);
+            erc721.settle(id).unwrap();
+            assert_eq!(erc721.owner_of_id(id), Some(accounts.charlie));
+            assert_eq!(balance(accounts.alice) - seller_before, 300 - DEFAULT_TRANSFER_FEE);
+            assert_eq!(erc721.escrowed_funds, 0);
+            assert_eq!(balance(accounts.frank), DEFAULT_TRANSFER_FEE);
+            assert_eq!(erc721.auction(id), None);
+        }
+
+        #[ink::test]
+        fn english_auction_without_bids_returns_token() {
+            let accounts = accounts();
+            let mut erc721 = new_contract();
+            set_value(DEFAULT_MINT_FEE);
+            erc721.mint(s("a"), s("a")).unwrap();
+            set_value(0);
+            let start = ink_env::block_timestamp::<Env>();
+            erc721.start_english_auction(s("a"), s("a"), 100, start + 60).unwrap();
+            let id = erc721.token_id(s("a"));
+
+            advance_to(start + 60);
+            set_caller(accounts.bob);
+            erc721.settle(id).unwrap();
+            assert_eq!(erc721.owner_of_id(id), Some(accounts.alice));
+            assert_eq!(erc721.tokens_of_owner(accounts.alice, 0, 10), ink_prelude::vec![id]);
+            assert_eq!(erc721.escrowed_funds, 0);
+            assert_eq!(erc721.settle(id), Err(Error::AuctionNotFound));
+        }
+
+        #[ink::test]
+        fn dutch_auction_refunds_overpaid_bid() {
+            let accounts = accounts();
+            let mut erc721 = new_contract();
+            set_value(DEFAULT_MINT_FEE);
+            erc721.mint(s("a"), s("a")).unwrap();
+            set_value(0);
+            let start = ink_env::block_timestamp::<Env>();
+            erc721.start_dutch_auction(s("a"), s("a"), 1_000, 100, 60).unwrap();
+            let id = erc721.token_id(s("a"));
+
+            // halfway the price dropped by half of 900
+            advance_to(start + 30);
+            assert_eq!(erc721.current_price(id), Some(550));
+            set_caller(accounts.charlie);
+            set_value(500);
+            assert_eq!(erc721.bid(id), Err(Error::BidTooLow));
+            set_value(700);
+            set_contract_balance(700);
+            let seller_before = balance(accounts.alice);
+            let buyer_before = balance(accounts.charlie);
+            erc721.bid(id).unwrap();
+            assert_eq!(erc721.owner_of_id(id), Some(accounts.charlie));
+            assert_eq!(balance(accounts.charlie) - buyer_before, 150);
+            assert_eq!(balance(accounts.alice) - seller_before, 550 - DEFAULT_TRANSFER_FEE);
+            assert_eq!(balance(accounts.frank), DEFAULT_TRANSFER_FEE);
+            assert_eq!(erc721.escrowed_funds, 0);
+            assert_eq!(erc721.auction(id), None);
+        }
+
+        #[ink::test]
+        fn sale_keeps_fee_with_full_royalty() {
+            let accounts = accounts();
+            let mut erc721 = new_contract();
+            set_caller(accounts.bob);
+            set_value(DEFAULT_MINT_FEE);
+            erc721.mint_with_royalty(s("a"), s("a"), Royalty {
+                receiver: accounts.bob,
+                rate: 10_000
+            }).unwrap();
+            set_value(0);
+            erc721.list(s("a"), s("a"), 1_000, 600).unwrap();
+
+            set_caller(accounts.charlie);
+            set_value(1_000);
+            set_contract_balance(1_000);
+            let seller_before = balance(accounts.bob);
+            erc721.buy(erc721.token_id(s("a"))).unwrap();
+            assert_eq!(balance(accounts.bob) - seller_before, 1_000 - DEFAULT_TRANSFER_FEE);
+            assert_eq!(balance(accounts.frank), DEFAULT_TRANSFER_FEE);
+            assert_eq!(erc721.owner_of(s("a")), Some(accounts.charlie));
+        }
+
+        #[ink::test]
+        fn payment_splitter_shares_collected_funds() {
+            let accounts = accounts();
+            let mut erc721 = new_contract();
+            assert_eq!(erc721.payees(), ink_prelude::vec![(accounts.alice, 1)]);
+            set_contract_balance(300);
+            assert_eq!(erc721.releasable(accounts.alice), 300);
+
+            for invalid in [
+                ink_prelude::vec![],
+                ink_prelude::vec![(accounts.bob, 0)],
+                ink_prelude::vec![(accounts.bob, 1), (accounts.bob, 2)]
+            ] {
+                assert_eq!(erc721.set_payees(invalid), Err(Error::InvalidPayees));
+            }
+            // what the founder earned so far is paid out first
+            let founder_before = balance(accounts.alice);
+            erc721.set_payees(ink_prelude::vec![(accounts.bob, 1), (accounts.charlie, 2)])
+                .unwrap();
+            assert_eq!(balance(accounts.alice) - founder_before, 300);
+            assert_eq!(erc721.release(accounts.alice), Err(Error::NotPayee));
+
+            set_contract_balance(300);
+            assert_eq!(erc721.releasable(accounts.bob), 100);
+            assert_eq!(erc721.releasable(accounts.charlie), 200);
+            let bob_before = balance(accounts.bob);
+            erc721.release(accounts.bob).unwrap();
+            assert_eq!(balance(accounts.bob) - bob_before, 100);
+            assert_eq!(erc721.released(accounts.bob), 100);
+            assert_eq!(erc721.release(accounts.bob), Err(Error::NoPaymentDue));
+
+            // bids are escrowed, not received
+            set_value(DEFAULT_MINT_FEE);
+            erc721.mint(s("a"), s("a")).unwrap();
+            set_value(0);
+            erc721.start_english_auction(s("a"), s("a"), 1_000, 600).unwrap();
+            set_caller(accounts.django);
+            set_value(1_000);
+            erc721.bid(erc721.token_id(s("a"))).unwrap();
+            set_contract_balance(200 + 1_000);
+            assert_eq!(erc721.releasable(accounts.bob), 0);
+            assert_eq!(erc721.releasable(accounts.charlie), 200);
+
+            set_contract_balance(500 + 1_000);
+            assert_eq!(erc721.releasable(accounts.bob), 100);
+            assert_eq!(erc721.releasable(accounts.charlie), 400);
+            set_value(0);
+            erc721.release(accounts.charlie).unwrap();
+            assert_eq!(erc721.released(accounts.charlie), 400);
+            assert_eq!(balance(accounts.frank), 1_100);
+            assert_eq!(erc721.releasable(accounts.bob), 100);
+        }
+
+        fn sign_voucher(
+            erc721: &Erc721,
+            secret: &secp256k1::SecretKey,
+            voucher: &MintVoucher
+        ) -> TokenSignature {
+            let mut message = [0u8; 32];
+            message.copy_from_slice(erc721.voucher_hash(voucher.clone()).as_ref());
+            sign(secret, &message)
+        }
+
+        #[ink::test]
+        fn voucher_signed_by_minter_key_redeems_once() {
+            let accounts = accounts();
+            let mut erc721 = new_contract();
+            let minter = secp256k1::SecretKey::from_slice(&[0x11; 32]).unwrap();
+            let stranger = secp256k1::SecretKey::from_slice(&[0x22; 32]).unwrap();
+            erc721.set_minter_key(public_key(&minter), true).unwrap();
+            let voucher = MintVoucher {
+                gen: s("v"),
+                recipient: accounts.bob,
+                price: 500,
+                expires_at: 1_000
+            };
+            let signature = sign_voucher(&erc721, &minter, &voucher);
+
+            set_caller(accounts.charlie);
+            set_value(500);
+            assert_eq!(
+                erc721.redeem_voucher(voucher.clone(), signature, s("p")),
+                Err(Error::NotVoucherRecipient)
+            );
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc721.redeem_voucher(
+                    voucher.clone(), sign_voucher(&erc721, &stranger, &voucher), s("p")
+                ),
+                Err(Error::InvalidSignature)
+            );
+            let discounted = MintVoucher { price: 0, ..voucher.clone() };
+            assert_eq!(
+                erc721.redeem_voucher(discounted, signature, s("p")),
+                Err(Error::InvalidSignature)
+            );
+            set_value(499);
+            assert_eq!(
+                erc721.redeem_voucher(voucher.clone(), signature, s("p")),
+                Err(Error::NotEnoughFee)
+            );
+
+            set_value(700);
+            set_contract_balance(700);
+            let bob_before = balance(accounts.bob);
+            erc721.redeem_voucher(voucher.clone(), signature, s("p")).unwrap();
+            assert_eq!(erc721.owner_of(s("v")), Some(accounts.bob));
+            assert_eq!(balance(accounts.bob) - bob_before, 200);
+            assert_eq!(balance(accounts.frank), 500);
+            assert!(erc721.voucher_redeemed(erc721.voucher_hash(voucher.clone())));
+            assert_eq!(
+                erc721.redeem_voucher(voucher.clone(), signature, s("p")),
+                Err(Error::VoucherAlreadyRedeemed)
+            );
+
+            set_caller(accounts.alice);
+            erc721.set_minter_key(public_key(&minter), false).unwrap();
+            let voucher = MintVoucher { gen: s("w"), price: 0, ..voucher };
+            let signature = sign_voucher(&erc721, &minter, &voucher);
+            set_caller(accounts.bob);
+            set_value(0);
+            assert_eq!(
+                erc721.redeem_voucher(voucher, signature, s("p")),
+                Err(Error::InvalidSignature)
+            );
+        }
+
+        #[ink::test]
+        fn voucher_signed_by_founder_expires() {
+            let accounts = accounts();
+            let founder_key = secp256k1::SecretKey::from_slice(&[0x33; 32]).unwrap();
+            let mut founder = [0u8; 32];
+            ink_env::hash_bytes::<GenHashing>(&public_key(&founder_key), &mut founder);
+            let founder = AccountId::from(founder);
+            ink_env::test::set_callee::<Env>(accounts.frank);
+            set_caller(founder);
+            let mut erc721 = Erc721::new(s("name"), s("symbol"));
+
+            let voucher = MintVoucher {
+                gen: s("v"),
+                recipient: accounts.bob,
+                price: 0,
+                expires_at: 60
+            };
+            let signature = sign_voucher(&erc721, &founder_key, &voucher);
+            let late = MintVoucher { gen: s("w"), ..voucher.clone() };
+            let late_signature = sign_voucher(&erc721, &founder_key, &late);
+
+            set_caller(accounts.bob);
+            erc721.redeem_voucher(voucher, signature, s("p")).unwrap();
+            assert_eq!(erc721.owner_of(s("v")), Some(accounts.bob));
+
+            advance_to(61);
+            assert_eq!(
+                erc721.redeem_voucher(late, late_signature, s("p")),
+                Err(Error::VoucherExpired)
+            );
+            assert_eq!(erc721.owner_of(s("w")), None);
+        }
+
+        #[ink::test]
+        fn burn_keeps_token_enumeration() {
+            let accounts = accounts();
+            let mut erc721 = new_contract();
+            set_value(DEFAULT_MINT_FEE);
+            for gen in ["a", "b", "c", "d"] {
+                erc721.mint(s(gen), s(gen)).unwrap();
+            }
+            let [a, _, c, d]: [TokenId; 4] = ids(&erc721, &["a", "b", "c", "d"])
+                .try_into()
+                .unwrap();
+
+            // the last token takes the burned one's index
+            set_value(DEFAULT_BURN_FEE);
+            erc721.burn(s("b"), s("b")).unwrap();
+            assert_eq!(erc721.total_supply(), 3);
+            assert_eq!(erc721.token_by_index(0), Some(a));
+            assert_eq!(erc721.token_by_index(1), Some(d));
+            assert_eq!(erc721.token_by_index(2), Some(c));
+            assert_eq!(erc721.token_by_index(3), None);
+            assert_eq!(erc721.balance_of(accounts.alice), 3);
+
+            erc721.burn(s("c"), s("c")).unwrap();
+            assert_eq!(erc721.total_supply(), 2);
+            assert_eq!(erc721.token_by_index(1), Some(d));
+            assert_eq!(erc721.token_by_index(2), None);
+            assert_eq!(erc721.tokens_of_owner(accounts.alice, 0, 10), ink_prelude::vec![a, d]);
+        }
+
+        // transfers value with the call and credits it to the contract.
+        fn pay(value: Balance) {
+            set_value(value);
+            set_contract_balance(balance(accounts().frank) + value);
+        }
+
+        #[ink::test]
+        fn fees_are_charged_and_overpayment_refunded() {
+            let accounts = accounts();
+            let mut erc721 = new_contract();
+            let fees = Fees { mint: 10, burn: 20, transfer: 30 };
+            set_caller(accounts.bob);
+            assert_eq!(erc721.set_fees(fees), Err(Error::FounderOnly));
+            set_caller(accounts.alice);
+            erc721.set_fees(fees).unwrap();
+            assert_eq!(erc721.fees(), fees);
+
+            set_caller(accounts.bob);
+            set_value(9);
+            assert_eq!(erc721.mint(s("a"), s("a")), Err(Error::NotEnoughFee));
+            pay(50);
+            let before = balance(accounts.bob);
+            erc721.mint(s("a"), s("a")).unwrap();
+            assert_eq!(balance(accounts.bob) - before, 40);
+
+            set_value(29);
+            assert_eq!(
+                erc721.transfer(accounts.charlie, s("a"), s("a")),
+                Err(Error::NotEnoughFee)
+            );
+            pay(100);
+            let before = balance(accounts.bob);
+            erc721.transfer(accounts.charlie, s("a"), s("a")).unwrap();
+            assert_eq!(balance(accounts.bob) - before, 70);
+
+            set_caller(accounts.charlie);
+            set_value(19);
+            assert_eq!(erc721.burn(s("a"), s("a")), Err(Error::NotEnoughFee));
+            pay(25);
+            let before = balance(accounts.charlie);
+            erc721.burn(s("a"), s("a")).unwrap();
+            assert_eq!(balance(accounts.charlie) - before, 5);
+            assert_eq!(balance(accounts.frank), 10 + 30 + 20);
+        }
+
+        #[ink::test]
+        fn fee_exempt_account_gets_everything_back() {
+            let accounts = accounts();
+            let mut erc721 = new_contract();
+            set_caller(accounts.bob);
+            assert_eq!(erc721.set_fee_exempt(accounts.bob, true), Err(Error::FounderOnly));
+            set_caller(accounts.alice);
+            erc721.set_fee_exempt(accounts.bob, true).unwrap();
+            assert!(erc721.is_fee_exempt(accounts.bob));
+
+            set_caller(accounts.bob);
+            set_value(0);
+            erc721.mint(s("a"), s("a")).unwrap();
+            pay(DEFAULT_MINT_FEE);
+            let before = balance(accounts.bob);
+            erc721.mint(s("b"), s("b")).unwrap();
+            assert_eq!(balance(accounts.bob) - before, DEFAULT_MINT_FEE);
+            pay(DEFAULT_TRANSFER_FEE);
+            erc721.transfer(accounts.charlie, s("a"), s("a")).unwrap();
+            pay(DEFAULT_BURN_FEE);
+            erc721.burn(s("b"), s("b")).unwrap();
+            assert_eq!(
+                balance(accounts.bob) - before,
+                DEFAULT_MINT_FEE + DEFAULT_TRANSFER_FEE + DEFAULT_BURN_FEE
+            );
+            assert_eq!(balance(accounts.frank), 0);
+
+            set_caller(accounts.alice);
+            erc721.set_fee_exempt(accounts.bob, false).unwrap();
+            set_caller(accounts.bob);
+            set_value(0);
+            assert_eq!(erc721.mint(s("c"), s("c")), Err(Error::NotEnoughFee));
+        }
+
+        #[ink::test]
+        fn locked_token_only_burns() {
+            let accounts = accounts();
+            let mut erc721 = new_contract();
+            set_caller(accounts.bob);
+            set_value(DEFAULT_MINT_FEE);
+            erc721.mint(s("u"), s("u")).unwrap();
+            set_caller(accounts.alice);
+            set_value(0);
+            erc721.mint_locked(accounts.bob, s("l"), s("l")).unwrap();
+            let id = erc721.token_id(s("l"));
+            assert_eq!(erc721.locked(id), Some(true));
+            assert_eq!(erc721.locked(erc721.token_id(s("u"))), Some(false));
+
+            set_caller(accounts.bob);
+            erc721.set_approval_for_all(accounts.charlie, true, pairs(&["u"])).unwrap();
+            assert_eq!(
+                erc721.set_approval_for_all(accounts.django, true, pairs(&["l"])),
+                Err(Error::TokenLocked)
+            );
+            assert_eq!(
+                erc721.approve(accounts.django, s("l"), s("l")),
+                Err(Error::TokenLocked)
+            );
+            assert_eq!(erc721.list(s("l"), s("l"), 1_000, 600), Err(Error::TokenLocked));
+
+            set_value(DEFAULT_TRANSFER_FEE);
+            assert_eq!(
+                erc721.transfer(accounts.django, s("l"), s("l")),
+                Err(Error::TokenLocked)
+            );
+            assert_eq!(
+                erc721.transfer_from(accounts.bob, accounts.django, s("l")),
+                Err(Error::TokenLocked)
+            );
+            set_value(2 * DEFAULT_TRANSFER_FEE);
+            assert_eq!(
+                erc721.transfer_batch(accounts.django, pairs(&["u", "l"])),
+                Err(Error::TokenLocked)
+            );
+            set_caller(accounts.charlie);
+            set_value(DEFAULT_TRANSFER_FEE);
+            assert_eq!(
+                erc721.transfer_from(accounts.bob, accounts.django, s("l")),
+                Err(Error::TokenLocked)
+            );
+            assert_eq!(erc721.owner_of(s("l")), Some(accounts.bob));
+            assert_eq!(erc721.owner_of(s("u")), Some(accounts.bob));
+
+            set_caller(accounts.bob);
+            set_value(DEFAULT_BURN_FEE);
+            assert_eq!(erc721.burn(s("l"), s("l")), Ok(()));
+            assert_eq!(erc721.owner_of(s("l")), None);
+            assert_eq!(erc721.locked(id), None);
+        }
+
+        #[ink::test]
+        fn burned_gen_cannot_be_minted_again() {
+            let accounts = accounts();
+            let mut erc721 = new_contract();
+            set_value(DEFAULT_MINT_FEE);
+            erc721.mint(s("a"), s("a")).unwrap();
+            let id = erc721.token_id(s("a"));
+            set_value(DEFAULT_BURN_FEE);
+            erc721.burn(s("a"), s("a")).unwrap();
+            assert!(erc721.is_burned(s("a")));
+
+            type Event = <Erc721 as ink_lang::reflect::ContractEventBase>::Type;
+            let burned = ink_env::test::recorded_events().last().unwrap();
+            match <Event as scale::Decode>::decode(&mut &burned.data[..]).unwrap() {
+                Event::Transfer(t) => {
+                    assert_eq!(t.from, Some(accounts.alice));
+                    assert_eq!(t.to, None);
+                    assert_eq!(t.id, id);
+                },
+                _ => panic!("expected a Transfer event")
+            }
+
+            set_value(DEFAULT_MINT_FEE);
+            assert_eq!(erc721.mint(s("a"), s("b")), Err(Error::TokenBurned));
+            set_caller(accounts.bob);
+            assert_eq!(erc721.mint_batch(pairs(&["a"])), Err(Error::TokenBurned));
+            assert_eq!(erc721.owner_of(s("a")), None);
+            assert_eq!(erc721.total_supply(), 0);
+        }
     }
 }